@@ -0,0 +1,30 @@
+//! Transport over the banks service, as `solana-program-test` provides
+
+use crate::{error::ClientResult, transport::Transport};
+use async_trait::async_trait;
+use solana_banks_client::BanksClient;
+use solana_program::{hash::Hash, pubkey::Pubkey};
+use solana_sdk::{account::Account, signature::Signature, transaction::Transaction};
+
+/// Requests go through a clone of the client, which shares its connection.
+/// The inherent methods are called by path as they share the trait names
+#[async_trait]
+impl Transport for BanksClient {
+    async fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Option<Account>> {
+        Ok(BanksClient::get_account(&mut self.clone(), *pubkey).await?)
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> ClientResult<u64> {
+        Ok(self.clone().get_rent().await?.minimum_balance(data_len))
+    }
+
+    async fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+        Ok(BanksClient::get_latest_blockhash(&mut self.clone()).await?)
+    }
+
+    async fn process_transaction(&self, transaction: Transaction) -> ClientResult<Signature> {
+        let signature = transaction.signatures[0];
+        BanksClient::process_transaction(&mut self.clone(), transaction).await?;
+        Ok(signature)
+    }
+}
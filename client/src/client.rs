@@ -0,0 +1,328 @@
+//! Typed requests to the data versioning program over any transport
+
+use crate::{
+    error::{ClientError, ClientResult},
+    state::{decode_config, StoredAccount},
+    transport::Transport,
+};
+use solana_data_versioning::{
+    account_state::{AccountAuthority, ACCOUNT_STATE_SPACE, DATA_VERSION},
+    config::{find_config_address, ProgramConfig},
+    instruction,
+};
+use solana_program::{
+    instruction::Instruction, program_error::ProgramError, pubkey::Pubkey, system_instruction,
+};
+use solana_sdk::{
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+
+/// Client of a deployment of the data versioning program. Transactions are
+/// paid by the payer given to each request and confirmed as the transport
+/// confirms them
+pub struct DataVersionClient<T> {
+    transport: T,
+    program_id: Pubkey,
+    auto_migrate: bool,
+}
+
+/// Data version change of an account
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Migration {
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+/// Outcome of a request updating an account
+#[derive(Clone, Debug, PartialEq)]
+pub struct UpdateOutcome {
+    pub signature: Signature,
+    /// Set when the client migrated the backlevel account first, see
+    /// `DataVersionClient::with_auto_migrate`
+    pub migration: Option<Migration>,
+}
+
+impl<T: Transport> DataVersionClient<T> {
+    /// Creates a client of the program deployed at `program_id`
+    pub fn new(transport: T, program_id: Pubkey) -> Self {
+        DataVersionClient {
+            transport,
+            program_id,
+            auto_migrate: false,
+        }
+    }
+
+    /// When set, updates of an account whose header shows a data version
+    /// below `DATA_VERSION` migrate it first in the same transaction, and
+    /// report the migration
+    pub fn with_auto_migrate(mut self, auto_migrate: bool) -> Self {
+        self.auto_migrate = auto_migrate;
+        self
+    }
+
+    /// The transport requests are sent with
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// The program the client targets
+    pub fn program_id(&self) -> &Pubkey {
+        &self.program_id
+    }
+
+    /// Signs the instructions with the payer and signers and sends them in
+    /// one transaction, returning once it is confirmed
+    pub async fn send_instructions(
+        &self,
+        instructions: &[Instruction],
+        payer: &dyn Signer,
+        signers: &[&dyn Signer],
+    ) -> ClientResult<Signature> {
+        let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+        let mut all_signers = vec![payer];
+        all_signers.extend_from_slice(signers);
+        let recent_blockhash = self.transport.get_latest_blockhash().await?;
+        transaction.try_sign(&all_signers, recent_blockhash)?;
+        self.transport.process_transaction(transaction).await
+    }
+
+    /// Sends an instruction updating the account, preceded by a
+    /// `MigrateAccount` when auto migrating a backlevel account
+    async fn send_update(
+        &self,
+        instruction: Instruction,
+        account: &Pubkey,
+        payer: &dyn Signer,
+        authorities: &[&dyn Signer],
+    ) -> ClientResult<UpdateOutcome> {
+        let from_version = if self.auto_migrate {
+            Some(self.stored_version(account).await?).filter(|version| *version < DATA_VERSION)
+        } else {
+            None
+        };
+        let mut instructions = Vec::with_capacity(2);
+        if from_version.is_some() {
+            instructions.push(instruction::migrate_account(
+                &self.program_id,
+                account,
+                &signer_keys(authorities).iter().collect::<Vec<_>>(),
+            ));
+        }
+        instructions.push(instruction);
+        let signature = self
+            .send_instructions(&instructions, payer, authorities)
+            .await?;
+        // A scheduled migration that is not active yet leaves the version
+        let migration = match from_version {
+            Some(from_version) => {
+                let to_version = self.stored_version(account).await?;
+                (to_version != from_version).then_some(Migration {
+                    from_version,
+                    to_version,
+                })
+            }
+            None => None,
+        };
+        Ok(UpdateOutcome {
+            signature,
+            migration,
+        })
+    }
+
+    /// Data version in the account header
+    async fn stored_version(&self, pubkey: &Pubkey) -> ClientResult<u8> {
+        let account = self
+            .transport
+            .get_account(pubkey)
+            .await?
+            .ok_or(ClientError::AccountNotFound(*pubkey))?;
+        account
+            .data
+            .get(1)
+            .copied()
+            .ok_or(ClientError::InvalidAccountData(
+                *pubkey,
+                ProgramError::AccountDataTooSmall,
+            ))
+    }
+
+    /// Creates and initializes a program account at the keypair address,
+    /// funded rent exempt by the payer, which anyone may update unless
+    /// given an authority
+    pub async fn create_account(
+        &self,
+        payer: &dyn Signer,
+        account: &dyn Signer,
+        somevalue: u64,
+        somestring: String,
+        authority: Option<AccountAuthority>,
+    ) -> ClientResult<Signature> {
+        let lamports = self
+            .transport
+            .get_minimum_balance_for_rent_exemption(ACCOUNT_STATE_SPACE)
+            .await?;
+        self.send_instructions(
+            &[
+                system_instruction::create_account(
+                    &payer.pubkey(),
+                    &account.pubkey(),
+                    lamports,
+                    ACCOUNT_STATE_SPACE as u64,
+                    &self.program_id,
+                ),
+                instruction::initialize_account(
+                    &self.program_id,
+                    &account.pubkey(),
+                    somevalue,
+                    somestring,
+                    authority,
+                ),
+            ],
+            payer,
+            &[account],
+        )
+        .await
+    }
+
+    /// Initializes a program account that was created and funded already,
+    /// which anyone may update unless given an authority
+    pub async fn initialize_account(
+        &self,
+        payer: &dyn Signer,
+        account: &Pubkey,
+        somevalue: u64,
+        somestring: String,
+        authority: Option<AccountAuthority>,
+    ) -> ClientResult<Signature> {
+        self.send_instructions(
+            &[instruction::initialize_account(
+                &self.program_id,
+                account,
+                somevalue,
+                somestring,
+                authority,
+            )],
+            payer,
+            &[],
+        )
+        .await
+    }
+
+    /// Reads the config, the default settings until the admin creates it
+    pub async fn get_config(&self) -> ClientResult<ProgramConfig> {
+        let (config_pubkey, _) = find_config_address(&self.program_id);
+        let config = self.transport.get_account(&config_pubkey).await?;
+        decode_config(&self.program_id, config.as_ref())
+    }
+
+    /// Reads a program account stored at any supported data version
+    pub async fn get_account(&self, pubkey: &Pubkey) -> ClientResult<StoredAccount> {
+        let account = self
+            .transport
+            .get_account(pubkey)
+            .await?
+            .ok_or(ClientError::AccountNotFound(*pubkey))?;
+        StoredAccount::decode(
+            &self.program_id,
+            pubkey,
+            &account,
+            &self.get_config().await?,
+        )
+    }
+
+    /// Sets somevalue, signed by the account authorities
+    pub async fn set_u64_value(
+        &self,
+        payer: &dyn Signer,
+        account: &Pubkey,
+        authorities: &[&dyn Signer],
+        value: u64,
+    ) -> ClientResult<UpdateOutcome> {
+        let instruction = instruction::set_u64_value(
+            &self.program_id,
+            account,
+            &signer_keys(authorities).iter().collect::<Vec<_>>(),
+            value,
+        );
+        self.send_update(instruction, account, payer, authorities)
+            .await
+    }
+
+    /// Sets somestring, signed by the account authorities
+    pub async fn set_string_value(
+        &self,
+        payer: &dyn Signer,
+        account: &Pubkey,
+        authorities: &[&dyn Signer],
+        value: String,
+    ) -> ClientResult<UpdateOutcome> {
+        let instruction = instruction::set_string_value(
+            &self.program_id,
+            account,
+            &signer_keys(authorities).iter().collect::<Vec<_>>(),
+            value,
+        );
+        self.send_update(instruction, account, payer, authorities)
+            .await
+    }
+
+    /// Replaces the account authority, signed by the current authorities
+    pub async fn set_authority(
+        &self,
+        payer: &dyn Signer,
+        account: &Pubkey,
+        authorities: &[&dyn Signer],
+        authority: AccountAuthority,
+    ) -> ClientResult<UpdateOutcome> {
+        let instruction = instruction::set_authority(
+            &self.program_id,
+            account,
+            &signer_keys(authorities).iter().collect::<Vec<_>>(),
+            authority,
+        );
+        self.send_update(instruction, account, payer, authorities)
+            .await
+    }
+
+    /// Migrates a backlevel account to the current data version, signed
+    /// by the account authorities
+    pub async fn migrate_account(
+        &self,
+        payer: &dyn Signer,
+        account: &Pubkey,
+        authorities: &[&dyn Signer],
+    ) -> ClientResult<Signature> {
+        let instruction = instruction::migrate_account(
+            &self.program_id,
+            account,
+            &signer_keys(authorities).iter().collect::<Vec<_>>(),
+        );
+        self.send_instructions(&[instruction], payer, authorities)
+            .await
+    }
+
+    /// Closes an account, signed by the account authorities, withdrawing
+    /// its lamports to the destination
+    pub async fn close_account(
+        &self,
+        payer: &dyn Signer,
+        account: &Pubkey,
+        authorities: &[&dyn Signer],
+        destination: &Pubkey,
+    ) -> ClientResult<Signature> {
+        let instruction = instruction::close_account(
+            &self.program_id,
+            account,
+            &signer_keys(authorities).iter().collect::<Vec<_>>(),
+            destination,
+        );
+        self.send_instructions(&[instruction], payer, authorities)
+            .await
+    }
+}
+
+/// Public keys of the signers
+fn signer_keys(signers: &[&dyn Signer]) -> Vec<Pubkey> {
+    signers.iter().map(|signer| signer.pubkey()).collect()
+}
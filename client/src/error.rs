@@ -0,0 +1,68 @@
+//! Client error enum
+
+#[cfg(feature = "rpc")]
+use solana_client::client_error::ClientError as RpcClientError;
+use solana_data_versioning::error::DataVersionError;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solana_sdk::{signer::SignerError, transaction::TransactionError, transport::TransportError};
+use std::io;
+use thiserror::Error;
+
+/// Errors of the client
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// The program failed the transaction with one of its errors
+    #[error("program error: {0}")]
+    Program(DataVersionError),
+    /// The transaction failed for another reason
+    #[error("transaction error: {0}")]
+    Transaction(TransactionError),
+    /// The request to the cluster failed, boxed as the RPC error is large
+    #[cfg(feature = "rpc")]
+    #[error("rpc error: {0}")]
+    Rpc(Box<RpcClientError>),
+    /// The transport failed to reach the cluster
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("signing failed: {0}")]
+    Signer(#[from] SignerError),
+    #[error("account {0} not found")]
+    AccountNotFound(Pubkey),
+    #[error("account {0} is not owned by the program")]
+    NotProgramAccount(Pubkey),
+    /// The account data does not decode at its stored version
+    #[error("account {0} could not be decoded: {1}")]
+    InvalidAccountData(Pubkey, ProgramError),
+}
+
+impl From<TransactionError> for ClientError {
+    fn from(error: TransactionError) -> Self {
+        match DataVersionError::from_transaction_error(&error) {
+            Some(error) => ClientError::Program(error),
+            None => ClientError::Transaction(error),
+        }
+    }
+}
+
+impl From<TransportError> for ClientError {
+    fn from(error: TransportError) -> Self {
+        match error {
+            TransportError::IoError(error) => error.into(),
+            TransportError::TransactionError(error) => error.into(),
+            TransportError::Custom(error) => io::Error::other(error).into(),
+        }
+    }
+}
+
+#[cfg(feature = "rpc")]
+impl From<RpcClientError> for ClientError {
+    fn from(error: RpcClientError) -> Self {
+        match error.get_transaction_error() {
+            Some(error) => error.into(),
+            None => ClientError::Rpc(Box::new(error)),
+        }
+    }
+}
+
+/// Result of client requests
+pub type ClientResult<T> = Result<T, ClientError>;
@@ -0,0 +1,22 @@
+//! Client SDK of the data versioning program, creating, reading and
+//! updating program accounts with typed requests over a `Transport`
+
+#[cfg(feature = "banks")]
+mod banks;
+pub mod client;
+pub mod error;
+#[cfg(feature = "mock")]
+pub mod mock;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+pub mod state;
+pub mod transport;
+
+pub use client::{DataVersionClient, Migration, UpdateOutcome};
+pub use error::{ClientError, ClientResult};
+#[cfg(feature = "mock")]
+pub use mock::MockTransport;
+#[cfg(feature = "rpc")]
+pub use rpc::RpcTransport;
+pub use state::StoredAccount;
+pub use transport::Transport;
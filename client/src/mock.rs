@@ -0,0 +1,284 @@
+//! In-memory transport running the program natively, for tests that start
+//! neither a validator nor the banks service
+
+use crate::{error::ClientResult, transport::Transport};
+use async_trait::async_trait;
+use solana_data_versioning::entry_point;
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::{ProgramResult, SUCCESS},
+    hash::Hash,
+    instruction::{CompiledInstruction, Instruction, InstructionError},
+    message::Message,
+    program_error::ProgramError,
+    program_stubs::{self, SyscallStubs},
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction::{SystemError, SystemInstruction},
+    system_program,
+};
+use solana_sdk::{
+    account::Account, packet::PACKET_DATA_SIZE, signature::Signature, transaction::Transaction,
+    transaction::TransactionError,
+};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, Once},
+};
+
+static INSTALL_SYSCALL_STUBS: Once = Once::new();
+
+/// Sysvars of the mock cluster, which never leaves slot 0
+struct MockSyscallStubs;
+
+impl SyscallStubs for MockSyscallStubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        _account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.sol_log(&format!(
+            "MockTransport: cannot invoke program {}",
+            instruction.program_id
+        ));
+        Err(ProgramError::IncorrectProgramId)
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Clock) = Clock::default() };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+}
+
+/// Cluster held in memory, processing transactions of the system program
+/// and the data versioning program. Transactions are all or nothing but
+/// charge no fees. Programs run natively with process wide syscall stubs,
+/// which the mock installs over those of `solana-program-test`, so do not
+/// mix the two in one test binary. Cross program invocations fail
+pub struct MockTransport {
+    program_id: Pubkey,
+    accounts: Mutex<HashMap<Pubkey, Account>>,
+}
+
+impl MockTransport {
+    /// Creates an empty cluster with the program deployed at `program_id`
+    pub fn new(program_id: Pubkey) -> Self {
+        INSTALL_SYSCALL_STUBS.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(MockSyscallStubs));
+        });
+        MockTransport {
+            program_id,
+            accounts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Stores an account, such as a funded payer or backlevel account data
+    pub fn set_account(&self, pubkey: Pubkey, account: Account) {
+        self.accounts.lock().unwrap().insert(pubkey, account);
+    }
+
+    fn process_instruction(
+        &self,
+        accounts: &mut HashMap<Pubkey, Account>,
+        message: &Message,
+        instruction: &CompiledInstruction,
+    ) -> Result<(), InstructionError> {
+        let program_id = message.account_keys[instruction.program_id_index as usize];
+        if program_id == system_program::id() {
+            process_system_instruction(accounts, message, instruction)
+        } else if program_id == self.program_id {
+            self.process_program_instruction(accounts, message, instruction)
+        } else {
+            Err(InstructionError::UnsupportedProgramId)
+        }
+    }
+
+    /// Runs the program on copies of the message accounts, storing those
+    /// it changed when the runtime would have accepted the changes
+    fn process_program_instruction(
+        &self,
+        accounts: &mut HashMap<Pubkey, Account>,
+        message: &Message,
+        instruction: &CompiledInstruction,
+    ) -> Result<(), InstructionError> {
+        let mut copies = message
+            .account_keys
+            .iter()
+            .map(|pubkey| accounts.get(pubkey).cloned().unwrap_or_default())
+            .collect::<Vec<_>>();
+        let result = {
+            let account_infos = message
+                .account_keys
+                .iter()
+                .zip(copies.iter_mut())
+                .enumerate()
+                .map(|(index, (pubkey, account))| {
+                    AccountInfo::new(
+                        pubkey,
+                        message.is_signer(index),
+                        message.is_writable(index),
+                        &mut account.lamports,
+                        &mut account.data,
+                        &account.owner,
+                        account.executable,
+                        account.rent_epoch,
+                    )
+                })
+                .collect::<Vec<_>>();
+            // Accounts listed more than once share one info, as on chain
+            let instruction_infos = instruction
+                .accounts
+                .iter()
+                .map(|index| account_infos[*index as usize].clone())
+                .collect::<Vec<_>>();
+            entry_point::process_instruction(
+                &self.program_id,
+                &instruction_infos,
+                &instruction.data,
+            )
+        };
+        result.map_err(|e| InstructionError::from(u64::from(e)))?;
+
+        let (mut lamports_before, mut lamports_after) = (0_u128, 0_u128);
+        for (index, (pubkey, account)) in message.account_keys.iter().zip(copies).enumerate() {
+            let original = accounts.get(pubkey).cloned().unwrap_or_default();
+            lamports_before += original.lamports as u128;
+            lamports_after += account.lamports as u128;
+            if account == original {
+                continue;
+            }
+            if !message.is_writable(index) {
+                return Err(if account.data != original.data {
+                    InstructionError::ReadonlyDataModified
+                } else {
+                    InstructionError::ReadonlyLamportChange
+                });
+            }
+            if original.owner != self.program_id {
+                if account.data != original.data {
+                    return Err(InstructionError::ExternalAccountDataModified);
+                }
+                if account.lamports < original.lamports {
+                    return Err(InstructionError::ExternalAccountLamportSpend);
+                }
+            }
+            accounts.insert(*pubkey, account);
+        }
+        if lamports_before != lamports_after {
+            return Err(InstructionError::UnbalancedInstruction);
+        }
+        Ok(())
+    }
+}
+
+/// Processes the system program instructions that fund accounts
+fn process_system_instruction(
+    accounts: &mut HashMap<Pubkey, Account>,
+    message: &Message,
+    instruction: &CompiledInstruction,
+) -> Result<(), InstructionError> {
+    let key = |position: usize| {
+        instruction
+            .accounts
+            .get(position)
+            .map(|index| {
+                let index = *index as usize;
+                (message.account_keys[index], message.is_signer(index))
+            })
+            .ok_or(InstructionError::NotEnoughAccountKeys)
+    };
+    let (from, from_signed) = key(0)?;
+    let (to, to_signed) = key(1)?;
+    match limited_deserialize(&instruction.data, PACKET_DATA_SIZE as u64)? {
+        SystemInstruction::CreateAccount {
+            lamports,
+            space,
+            owner,
+        } => {
+            if !from_signed || !to_signed {
+                return Err(InstructionError::MissingRequiredSignature);
+            }
+            if accounts.contains_key(&to) {
+                return Err(InstructionError::Custom(
+                    SystemError::AccountAlreadyInUse as u32,
+                ));
+            }
+            withdraw(accounts, &from, lamports)?;
+            accounts.insert(
+                to,
+                Account {
+                    lamports,
+                    data: vec![0; space as usize],
+                    owner,
+                    ..Account::default()
+                },
+            );
+        }
+        SystemInstruction::Transfer { lamports } => {
+            if !from_signed {
+                return Err(InstructionError::MissingRequiredSignature);
+            }
+            withdraw(accounts, &from, lamports)?;
+            accounts.entry(to).or_default().lamports += lamports;
+        }
+        _ => return Err(InstructionError::InvalidInstructionData),
+    }
+    Ok(())
+}
+
+/// Takes lamports from a system account
+fn withdraw(
+    accounts: &mut HashMap<Pubkey, Account>,
+    pubkey: &Pubkey,
+    lamports: u64,
+) -> Result<(), InstructionError> {
+    let account = accounts
+        .get_mut(pubkey)
+        .filter(|account| account.lamports >= lamports)
+        .ok_or(InstructionError::Custom(
+            SystemError::ResultWithNegativeLamports as u32,
+        ))?;
+    account.lamports -= lamports;
+    Ok(())
+}
+
+#[async_trait]
+impl Transport for MockTransport {
+    async fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Option<Account>> {
+        Ok(self.accounts.lock().unwrap().get(pubkey).cloned())
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> ClientResult<u64> {
+        Ok(Rent::default().minimum_balance(data_len))
+    }
+
+    async fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+        Ok(Hash::default())
+    }
+
+    async fn process_transaction(&self, transaction: Transaction) -> ClientResult<Signature> {
+        transaction.verify()?;
+        let message = &transaction.message;
+        let mut stored = self.accounts.lock().unwrap();
+        if !stored.contains_key(&message.account_keys[0]) {
+            return Err(TransactionError::AccountNotFound.into());
+        }
+        let mut accounts = stored.clone();
+        for (index, instruction) in message.instructions.iter().enumerate() {
+            self.process_instruction(&mut accounts, message, instruction)
+                .map_err(|e| TransactionError::InstructionError(index as u8, e))?;
+        }
+        // Accounts left without lamports are gone, as closed accounts are
+        accounts.retain(|_, account| account.lamports > 0);
+        *stored = accounts;
+        Ok(transaction.signatures[0])
+    }
+}
@@ -0,0 +1,71 @@
+//! Transport over the JSON RPC API of a cluster
+
+use crate::{error::ClientResult, transport::Transport};
+use async_trait::async_trait;
+use solana_client::rpc_client::RpcClient;
+use solana_program::{hash::Hash, pubkey::Pubkey};
+use solana_sdk::{account::Account, signature::Signature, transaction::Transaction};
+use std::{io, sync::Arc};
+use tokio::task;
+
+/// Transport sending requests through an `RpcClient` at its commitment.
+/// solana-client 1.9 has no nonblocking client, so each request runs on
+/// the blocking thread pool of the tokio runtime
+#[derive(Clone)]
+pub struct RpcTransport {
+    rpc_client: Arc<RpcClient>,
+}
+
+impl RpcTransport {
+    pub fn new(rpc_client: RpcClient) -> Self {
+        RpcTransport {
+            rpc_client: Arc::new(rpc_client),
+        }
+    }
+
+    /// The RPC client requests are sent with
+    pub fn rpc_client(&self) -> &RpcClient {
+        &self.rpc_client
+    }
+
+    /// Runs a blocking request off the async threads, also for requests
+    /// the `Transport` trait does not cover
+    pub async fn run<T, F>(&self, request: F) -> ClientResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&RpcClient) -> ClientResult<T> + Send + 'static,
+    {
+        let rpc_client = self.rpc_client.clone();
+        task::spawn_blocking(move || request(&rpc_client))
+            .await
+            .map_err(io::Error::other)?
+    }
+}
+
+#[async_trait]
+impl Transport for RpcTransport {
+    async fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Option<Account>> {
+        let pubkey = *pubkey;
+        self.run(move |rpc_client| {
+            Ok(rpc_client
+                .get_account_with_commitment(&pubkey, rpc_client.commitment())?
+                .value)
+        })
+        .await
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> ClientResult<u64> {
+        self.run(move |rpc_client| Ok(rpc_client.get_minimum_balance_for_rent_exemption(data_len)?))
+            .await
+    }
+
+    async fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+        self.run(|rpc_client| Ok(rpc_client.get_latest_blockhash()?))
+            .await
+    }
+
+    async fn process_transaction(&self, transaction: Transaction) -> ClientResult<Signature> {
+        self.run(move |rpc_client| Ok(rpc_client.send_and_confirm_transaction(&transaction)?))
+            .await
+    }
+}
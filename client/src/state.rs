@@ -0,0 +1,60 @@
+//! Decoding of the program and config accounts as fetched from a cluster
+
+use crate::error::{ClientError, ClientResult};
+use solana_data_versioning::{
+    account_state::ProgramAccountState,
+    config::{find_config_address, ProgramConfig},
+};
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+use solana_sdk::account::Account;
+
+/// Program account decoded to the current layout, whatever data version
+/// it is stored at
+#[derive(Debug, PartialEq)]
+pub struct StoredAccount {
+    pub pubkey: Pubkey,
+    pub lamports: u64,
+    /// Data version of the stored bytes, which the next write migrates
+    /// when older than `DATA_VERSION`
+    pub stored_version: u8,
+    /// The state as the program reads it, backlevel fields taking the
+    /// config defaults
+    pub state: ProgramAccountState,
+}
+
+impl StoredAccount {
+    /// Decodes the program account under the config, as the program does
+    /// before processing an instruction
+    pub fn decode(
+        program_id: &Pubkey,
+        pubkey: &Pubkey,
+        account: &Account,
+        config: &ProgramConfig,
+    ) -> ClientResult<Self> {
+        if account.owner != *program_id {
+            return Err(ClientError::NotProgramAccount(*pubkey));
+        }
+        let state =
+            ProgramAccountState::unpack_with_defaults(&account.data, &config.field_defaults)
+                .map_err(|e| ClientError::InvalidAccountData(*pubkey, e))?;
+        Ok(StoredAccount {
+            pubkey: *pubkey,
+            lamports: account.lamports,
+            stored_version: account.data[1],
+            state,
+        })
+    }
+}
+
+/// Decodes the config account, which has the default settings until the
+/// admin creates it
+pub fn decode_config(
+    program_id: &Pubkey,
+    account: Option<&Account>,
+) -> ClientResult<ProgramConfig> {
+    match account {
+        Some(account) if account.owner == *program_id => ProgramConfig::unpack(&account.data)
+            .map_err(|e| ClientError::InvalidAccountData(find_config_address(program_id).0, e)),
+        _ => Ok(ProgramConfig::default()),
+    }
+}
@@ -0,0 +1,24 @@
+//! Transports carrying the client requests to a cluster. Implementations
+//! exist for RPC (feature `rpc`), `BanksClient` (feature `banks`) and an
+//! in-memory mock (feature `mock`)
+
+use crate::error::ClientResult;
+use async_trait::async_trait;
+use solana_program::{hash::Hash, pubkey::Pubkey};
+use solana_sdk::{account::Account, signature::Signature, transaction::Transaction};
+
+/// Requests the client makes of a cluster
+#[async_trait]
+pub trait Transport: Send + Sync {
+    /// Fetches an account, None if it does not exist
+    async fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Option<Account>>;
+
+    /// Lamports that make an account of `data_len` bytes rent exempt
+    async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> ClientResult<u64>;
+
+    /// Blockhash to sign transactions with
+    async fn get_latest_blockhash(&self) -> ClientResult<Hash>;
+
+    /// Processes a signed transaction, returning once it is confirmed
+    async fn process_transaction(&self, transaction: Transaction) -> ClientResult<Signature>;
+}
@@ -0,0 +1,29 @@
+//! Client test suite over BanksClient
+
+#[macro_use]
+mod common;
+
+use common::{backlevel_account, Fixture};
+use solana_banks_client::BanksClient;
+use solana_data_versioning::entry_point;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{processor, ProgramTest};
+use versioning_solana_client::DataVersionClient;
+
+async fn setup() -> Fixture<BanksClient> {
+    let mut program_test = ProgramTest::new(
+        "solana_data_versioning",
+        solana_data_versioning::id(),
+        processor!(entry_point::process_instruction),
+    );
+    let backlevel_pubkey = Pubkey::new_unique();
+    program_test.add_account(backlevel_pubkey, backlevel_account());
+    let (banks_client, payer, _) = program_test.start().await;
+    Fixture {
+        client: DataVersionClient::new(banks_client, solana_data_versioning::id()),
+        payer,
+        backlevel_pubkey,
+    }
+}
+
+client_tests!(setup);
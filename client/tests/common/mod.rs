@@ -0,0 +1,186 @@
+//! Client test suite shared by the transports
+
+use solana_data_versioning::{
+    account_state::{AccountAuthority, ACCOUNT_STATE_SPACE, DATA_VERSION},
+    error::DataVersionError,
+};
+use solana_program::{pubkey::Pubkey, rent::Rent};
+use solana_sdk::{account::Account, signature::Keypair, signer::Signer};
+use versioning_solana_client::{ClientError, DataVersionClient, Migration, Transport};
+
+/// Client with a funded payer and an account at DATA_VERSION 0
+pub struct Fixture<T> {
+    pub client: DataVersionClient<T>,
+    pub payer: Keypair,
+    pub backlevel_pubkey: Pubkey,
+}
+
+/// Somevalue of the DATA_VERSION 0 account
+pub const BACKLEVEL_VALUE: u64 = 41;
+
+/// Account stored at DATA_VERSION 0 layout: initialized, version, somevalue
+pub fn backlevel_account() -> Account {
+    let mut data = vec![0_u8; ACCOUNT_STATE_SPACE];
+    data[0] = 1;
+    data[2..10].copy_from_slice(&BACKLEVEL_VALUE.to_le_bytes());
+    Account {
+        lamports: Rent::default().minimum_balance(ACCOUNT_STATE_SPACE),
+        data,
+        owner: solana_data_versioning::id(),
+        ..Account::default()
+    }
+}
+
+/// Declares the suite tests, each run on the fixture `$setup` returns
+macro_rules! client_tests {
+    ($setup:ident) => {
+        #[tokio::test]
+        async fn test_account_lifecycle_pass() {
+            common::account_lifecycle_pass($setup().await).await
+        }
+
+        #[tokio::test]
+        async fn test_backlevel_account_pass() {
+            common::backlevel_account_pass($setup().await).await
+        }
+
+        #[tokio::test]
+        async fn test_auto_migrate_pass() {
+            common::auto_migrate_pass($setup().await).await
+        }
+
+        #[tokio::test]
+        async fn test_client_errors_fail() {
+            common::client_errors_fail($setup().await).await
+        }
+    };
+}
+
+/// Creates, updates and closes an account
+pub async fn account_lifecycle_pass<T: Transport>(fixture: Fixture<T>) {
+    let Fixture { client, payer, .. } = fixture;
+    let account = Keypair::new();
+    client
+        .create_account(&payer, &account, 1, String::new(), None)
+        .await
+        .unwrap();
+    let stored = client.get_account(&account.pubkey()).await.unwrap();
+    assert_eq!(stored.stored_version, DATA_VERSION);
+    assert!(stored.state.initialized());
+    assert_eq!(stored.state.content().somevalue, 1);
+
+    let outcome = client
+        .set_u64_value(&payer, &account.pubkey(), &[], 42)
+        .await
+        .unwrap();
+    assert_eq!(outcome.migration, None);
+    client
+        .set_string_value(&payer, &account.pubkey(), &[], String::from("Hello"))
+        .await
+        .unwrap();
+    let stored = client.get_account(&account.pubkey()).await.unwrap();
+    assert_eq!(stored.state.content().somevalue, 42);
+    assert_eq!(stored.state.content().somestring, "Hello");
+    assert_eq!(stored.state.write_sequence(), 3);
+
+    // Closing takes the authority
+    let authority = Keypair::new();
+    client
+        .set_authority(
+            &payer,
+            &account.pubkey(),
+            &[],
+            AccountAuthority::Single(authority.pubkey()),
+        )
+        .await
+        .unwrap();
+    client
+        .close_account(&payer, &account.pubkey(), &[&authority], &payer.pubkey())
+        .await
+        .unwrap();
+    assert!(matches!(
+        client.get_account(&account.pubkey()).await,
+        Err(ClientError::AccountNotFound(pubkey)) if pubkey == account.pubkey()
+    ));
+}
+
+/// Reads an account at its stored version, then migrates it
+pub async fn backlevel_account_pass<T: Transport>(fixture: Fixture<T>) {
+    let Fixture {
+        client,
+        payer,
+        backlevel_pubkey,
+    } = fixture;
+    let stored = client.get_account(&backlevel_pubkey).await.unwrap();
+    assert_eq!(stored.stored_version, 0);
+    assert_eq!(stored.state.content().somevalue, BACKLEVEL_VALUE);
+    assert_eq!(stored.state.content().somestring, "");
+
+    client
+        .migrate_account(&payer, &backlevel_pubkey, &[])
+        .await
+        .unwrap();
+    let stored = client.get_account(&backlevel_pubkey).await.unwrap();
+    assert_eq!(stored.stored_version, DATA_VERSION);
+    assert_eq!(stored.state.content().somevalue, BACKLEVEL_VALUE);
+}
+
+/// Migrates a backlevel account ahead of its first update
+pub async fn auto_migrate_pass<T: Transport>(fixture: Fixture<T>) {
+    let Fixture {
+        client,
+        payer,
+        backlevel_pubkey,
+    } = fixture;
+    let client = client.with_auto_migrate(true);
+    let outcome = client
+        .set_string_value(&payer, &backlevel_pubkey, &[], String::from("Hello"))
+        .await
+        .unwrap();
+    assert_eq!(
+        outcome.migration,
+        Some(Migration {
+            from_version: 0,
+            to_version: DATA_VERSION,
+        })
+    );
+    let stored = client.get_account(&backlevel_pubkey).await.unwrap();
+    assert_eq!(stored.stored_version, DATA_VERSION);
+    assert_eq!(stored.state.content().somevalue, BACKLEVEL_VALUE);
+    assert_eq!(stored.state.content().somestring, "Hello");
+
+    // Current accounts are updated without migrating
+    let outcome = client
+        .set_u64_value(&payer, &backlevel_pubkey, &[], 42)
+        .await
+        .unwrap();
+    assert_eq!(outcome.migration, None);
+}
+
+/// Surfaces program errors and missing accounts
+pub async fn client_errors_fail<T: Transport>(fixture: Fixture<T>) {
+    let Fixture {
+        client,
+        payer,
+        backlevel_pubkey,
+    } = fixture;
+    let result = client
+        .initialize_account(&payer, &backlevel_pubkey, 1, String::new(), None)
+        .await;
+    assert!(matches!(
+        result,
+        Err(ClientError::Program(
+            DataVersionError::AlreadyInitializedState
+        ))
+    ));
+
+    let missing = Pubkey::new_unique();
+    assert!(matches!(
+        client.get_account(&missing).await,
+        Err(ClientError::AccountNotFound(pubkey)) if pubkey == missing
+    ));
+    assert!(matches!(
+        client.get_account(&payer.pubkey()).await,
+        Err(ClientError::NotProgramAccount(_))
+    ));
+}
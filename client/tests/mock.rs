@@ -0,0 +1,30 @@
+//! Client test suite over the in-memory transport
+
+#[macro_use]
+mod common;
+
+use common::{backlevel_account, Fixture};
+use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_sdk::{account::Account, signature::Keypair, signer::Signer};
+use versioning_solana_client::{DataVersionClient, MockTransport};
+
+async fn setup() -> Fixture<MockTransport> {
+    let transport = MockTransport::new(solana_data_versioning::id());
+    let payer = Keypair::new();
+    transport.set_account(
+        payer.pubkey(),
+        Account {
+            lamports: 10 * LAMPORTS_PER_SOL,
+            ..Account::default()
+        },
+    );
+    let backlevel_pubkey = Pubkey::new_unique();
+    transport.set_account(backlevel_pubkey, backlevel_account());
+    Fixture {
+        client: DataVersionClient::new(transport, solana_data_versioning::id()),
+        payer,
+        backlevel_pubkey,
+    }
+}
+
+client_tests!(setup);
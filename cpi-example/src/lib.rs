@@ -0,0 +1,181 @@
+//! Example program that reads and updates versioned accounts through
+//! CPI to the data versioning program. The target accounts it updates
+//! have this program's authority address as their authority
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_data_versioning::{config::find_config_address, cpi, instruction::ValueBounds};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+solana_program::declare_id!("25GJNztfHTSuoCjmVz5DowiRhs93qGWudoz9jDzBZ3b8");
+
+/// Seed of the address this program signs versioned account updates with
+pub const AUTHORITY_SEED: &[u8] = b"authority";
+
+/// Finds the authority address of this program
+pub fn find_authority_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AUTHORITY_SEED], program_id)
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, PartialEq)]
+/// Example program instructions
+pub enum ExampleInstruction {
+    /// Copies somevalue of the source account, read at whatever data
+    /// version it is stored, to the target account
+    MirrorValue,
+    /// Increments somevalue of the target account up to `max`
+    IncrementValue { max: u64 },
+}
+
+/// Creates a `MirrorValue` instruction
+///
+/// Accounts expected:
+///   0. `[]` The data versioning program
+///   1. `[]` The source program account
+///   2. `[writable]` The target program account
+///   3. `[]` The data versioning config account
+///   4. `[]` The authority address, see `find_authority_address`
+pub fn mirror_value(
+    versioning_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    target_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &ExampleInstruction::MirrorValue,
+        vec![
+            AccountMeta::new_readonly(*versioning_program_id, false),
+            AccountMeta::new_readonly(*source_pubkey, false),
+            AccountMeta::new(*target_pubkey, false),
+            AccountMeta::new_readonly(find_config_address(versioning_program_id).0, false),
+            AccountMeta::new_readonly(find_authority_address(&id()).0, false),
+        ],
+    )
+}
+
+/// Creates an `IncrementValue` instruction
+///
+/// Accounts expected:
+///   0. `[]` The data versioning program
+///   1. `[writable]` The target program account
+///   2. `[]` The data versioning config account
+///   3. `[]` The authority address, see `find_authority_address`
+pub fn increment_value(
+    versioning_program_id: &Pubkey,
+    target_pubkey: &Pubkey,
+    max: u64,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &ExampleInstruction::IncrementValue { max },
+        vec![
+            AccountMeta::new_readonly(*versioning_program_id, false),
+            AccountMeta::new(*target_pubkey, false),
+            AccountMeta::new_readonly(find_config_address(versioning_program_id).0, false),
+            AccountMeta::new_readonly(find_authority_address(&id()).0, false),
+        ],
+    )
+}
+
+/// Confirms the versioning program account is the data versioning
+/// program, which the authority address signs for, and the authority
+/// account is this program's authority address, returning its bump seed
+fn authority_bump_seed(
+    program_id: &Pubkey,
+    versioning_program: &AccountInfo,
+    authority: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    if *versioning_program.key != solana_data_versioning::id() {
+        msg!(
+            "Fail: Account {} is not the data versioning program",
+            versioning_program.key
+        );
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (address, bump_seed) = find_authority_address(program_id);
+    if address != *authority.key {
+        msg!(
+            "Fail: Account {} is not the authority address",
+            authority.key
+        );
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(bump_seed)
+}
+
+/// Reads the source value through GetState and sets it on the target
+fn process_mirror_value(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let versioning_program = next_account_info(account_info_iter)?;
+    let source = next_account_info(account_info_iter)?;
+    let target = next_account_info(account_info_iter)?;
+    let config = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let bump_seed = authority_bump_seed(program_id, versioning_program, authority)?;
+    let content = cpi::get_state(versioning_program, source, config)?;
+    msg!("Mirror value {}", content.somevalue);
+    cpi::set_u64_value(
+        &cpi::UpdateAccounts {
+            program: versioning_program,
+            account: target,
+            config,
+            signers: std::slice::from_ref(authority),
+        },
+        content.somevalue,
+        &[&[AUTHORITY_SEED, &[bump_seed]]],
+    )
+}
+
+/// Increments the target value through IncrementU64
+fn process_increment_value(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    max: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let versioning_program = next_account_info(account_info_iter)?;
+    let target = next_account_info(account_info_iter)?;
+    let config = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let bump_seed = authority_bump_seed(program_id, versioning_program, authority)?;
+    let value = cpi::increment_u64(
+        &cpi::UpdateAccounts {
+            program: versioning_program,
+            account: target,
+            config,
+            signers: std::slice::from_ref(authority),
+        },
+        ValueBounds {
+            min: None,
+            max: Some(max),
+        },
+        &[&[AUTHORITY_SEED, &[bump_seed]]],
+    )?;
+    msg!("Incremented value to {}", value);
+    Ok(())
+}
+
+/// Dispatches the example instructions
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = ExampleInstruction::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    match instruction {
+        ExampleInstruction::MirrorValue => process_mirror_value(program_id, accounts),
+        ExampleInstruction::IncrementValue { max } => {
+            process_increment_value(program_id, accounts, max)
+        }
+    }
+}
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
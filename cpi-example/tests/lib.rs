@@ -0,0 +1,183 @@
+//! test reading and updating versioned accounts through CPI
+
+use solana_data_versioning::{
+    account_state::{AccountAuthority, ProgramAccountState, ACCOUNT_STATE_SPACE, DATA_VERSION},
+    entry_point,
+    error::DataVersionError,
+    instruction::{initialize_account, set_authority},
+};
+use solana_program::{
+    borsh::try_from_slice_unchecked, instruction::Instruction, pubkey::Pubkey, rent::Rent,
+};
+use solana_program_test::{processor, tokio, BanksClient, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    signature::Keypair,
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
+    transport::TransportError,
+};
+use versioning_cpi_example::{find_authority_address, increment_value, mirror_value};
+
+/// Submit transaction with the instructions, paid by the payer
+async fn submit_txn(
+    instructions: &[Instruction],
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+) -> Result<(), TransportError> {
+    let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    transaction.sign(&[payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await
+}
+
+/// Reads the data version and somevalue of a current version account
+async fn somevalue(banks_client: &mut BanksClient, pubkey: Pubkey) -> (u8, u64) {
+    let account = banks_client.get_account(pubkey).await.unwrap().unwrap();
+    let state = try_from_slice_unchecked::<ProgramAccountState>(&account.data).unwrap();
+    (account.data[1], state.content().somevalue)
+}
+
+/// Program test running the example and data versioning programs
+fn program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::new(
+        "versioning_cpi_example",
+        versioning_cpi_example::id(),
+        processor!(versioning_cpi_example::process_instruction),
+    );
+    program_test.add_program(
+        "solana_data_versioning",
+        solana_data_versioning::id(),
+        processor!(entry_point::process_instruction),
+    );
+    program_test
+}
+
+#[tokio::test]
+/// Validates the example program reads a backlevel account and updates
+/// an account it is the authority of
+async fn test_cpi_example_pass() {
+    let versioning_id = solana_data_versioning::id();
+    let mut program_test = program_test();
+    // Source at DATA_VERSION 0 layout: initialized, version, somevalue
+    let source_pubkey = Pubkey::new_unique();
+    let mut data = vec![0_u8; ACCOUNT_STATE_SPACE];
+    data[0] = 1;
+    data[2..10].copy_from_slice(&41u64.to_le_bytes());
+    let target_pubkey = Pubkey::new_unique();
+    for (pubkey, data) in [
+        (source_pubkey, data),
+        (target_pubkey, vec![0_u8; ACCOUNT_STATE_SPACE]),
+    ] {
+        program_test.add_account(
+            pubkey,
+            Account {
+                lamports: Rent::default().minimum_balance(ACCOUNT_STATE_SPACE),
+                data,
+                owner: versioning_id,
+                ..Account::default()
+            },
+        );
+    }
+    let (mut banks_client, payer, _) = program_test.start().await;
+
+    // The target is updated by the example program only
+    let (authority_pubkey, _) = find_authority_address(&versioning_cpi_example::id());
+    let result = submit_txn(
+        &[
+            initialize_account(&versioning_id, &target_pubkey, 1, String::new(), None),
+            set_authority(
+                &versioning_id,
+                &target_pubkey,
+                &[],
+                AccountAuthority::Single(authority_pubkey),
+            ),
+        ],
+        &payer,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    let result = submit_txn(
+        &[mirror_value(&versioning_id, &source_pubkey, &target_pubkey)],
+        &payer,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    assert_eq!(
+        somevalue(&mut banks_client, target_pubkey).await,
+        (DATA_VERSION, 41)
+    );
+    // Reading left the source at its stored version
+    let source = banks_client
+        .get_account(source_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(source.data[1], 0);
+
+    let result = submit_txn(
+        &[increment_value(&versioning_id, &target_pubkey, 43)],
+        &payer,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    assert_eq!(
+        somevalue(&mut banks_client, target_pubkey).await,
+        (DATA_VERSION, 42)
+    );
+
+    let result = submit_txn(
+        &[increment_value(&versioning_id, &target_pubkey, 42)],
+        &payer,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(DataVersionError::ValueOutOfBounds as u32)
+        )
+    );
+}
+
+#[tokio::test]
+/// Validates the example program only invokes the data versioning program
+/// with its authority signature
+async fn test_cpi_example_wrong_program_fail() {
+    let mut program_test = program_test();
+    // An impostor deployed at another address
+    let impostor_id = Pubkey::new_unique();
+    program_test.add_program(
+        "solana_data_versioning",
+        impostor_id,
+        processor!(entry_point::process_instruction),
+    );
+    let target_pubkey = Pubkey::new_unique();
+    program_test.add_account(
+        target_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(ACCOUNT_STATE_SPACE),
+            data: vec![0_u8; ACCOUNT_STATE_SPACE],
+            owner: impostor_id,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, _) = program_test.start().await;
+
+    for instruction in [
+        mirror_value(&impostor_id, &target_pubkey, &target_pubkey),
+        increment_value(&impostor_id, &target_pubkey, 1),
+    ] {
+        let result = submit_txn(&[instruction], &payer, &mut banks_client).await;
+        assert_eq!(
+            result.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+        );
+    }
+}
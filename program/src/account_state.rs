@@ -4,19 +4,42 @@ use arrayref::{array_ref, array_refs};
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     borsh::try_from_slice_unchecked,
-    msg,
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
 };
-use std::{io::BufWriter, mem};
+use std::{collections::BTreeMap, io::Write, mem};
 
-/// Current state (DATA_VERSION 1). If version changes occur, this
-/// should be copied to another (see AccountContentOld below)
-/// We've added a new field: 'somestring'
-#[derive(BorshDeserialize, BorshSerialize, Debug, Default, PartialEq)]
+use crate::{
+    error::DataVersionError,
+    logging::{log_debug, log_error},
+};
+
+/// Current state (DATA_VERSION 5). If version changes occur, this
+/// should be copied to another (see AccountContentV1 below)
+/// We've added a bounded map of named typed values: 'fields'
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Default, PartialEq)]
 pub struct AccountContentCurrent {
     pub somevalue: u64,
     pub somestring: String,
+    pub fields: BTreeMap<String, FieldValue>,
+}
+
+/// Content state (DATA_VERSION 1 to 4), which added 'somestring'
+#[derive(BorshDeserialize, BorshSerialize, Debug, Default, PartialEq)]
+pub struct AccountContentV1 {
+    pub somevalue: u64,
+    pub somestring: String,
+}
+
+impl From<AccountContentV1> for AccountContentCurrent {
+    fn from(old: AccountContentV1) -> Self {
+        AccountContentCurrent {
+            somevalue: old.somevalue,
+            somestring: old.somestring,
+            ..AccountContentCurrent::default()
+        }
+    }
 }
 
 /// Old content state (DATA_VERSION 0).
@@ -25,27 +48,209 @@ pub struct AccountContentOld {
     pub somevalue: u64,
 }
 
+/// Typed value of a named field
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq)]
+pub enum FieldValue {
+    U64(u64),
+    I64(i64),
+    Bool(bool),
+    String(String),
+    Pubkey(Pubkey),
+}
+
+/// Maximum number of named fields in the content
+pub const MAX_FIELDS: usize = 16;
+/// Longest field name
+pub const MAX_FIELD_KEY_LEN: usize = 32;
+/// Longest string field value
+pub const MAX_FIELD_STRING_LEN: usize = 64;
+
+/// Values given to fields that did not exist when an account was
+/// written, used when converting backlevel data
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Default, PartialEq)]
+pub struct FieldDefaults {
+    /// Added with DATA_VERSION 1
+    pub somestring: String,
+}
+
+/// Maximum number of keys a multisig authority may list
+pub const MAX_SIGNERS: usize = 11;
+
+/// Who may mutate the account (DATA_VERSION 2)
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq)]
+pub enum AccountAuthority {
+    /// Anyone may mutate the account, as was the case before DATA_VERSION 2
+    None,
+    /// The key must sign any mutation
+    Single(Pubkey),
+    /// At least `threshold` of the `signers` must sign any mutation
+    Multisig { threshold: u8, signers: Vec<Pubkey> },
+}
+
+// Deriving with `#[default]` needs a newer rustc than the BPF toolchain
+#[allow(clippy::derivable_impls)]
+impl Default for AccountAuthority {
+    fn default() -> Self {
+        AccountAuthority::None
+    }
+}
+
+impl AccountAuthority {
+    /// Checks that a multisig threshold is reachable and its signers are
+    /// unique and within MAX_SIGNERS
+    pub fn is_valid(&self) -> bool {
+        match self {
+            AccountAuthority::None | AccountAuthority::Single(_) => true,
+            AccountAuthority::Multisig { threshold, signers } => {
+                let unique = signers
+                    .iter()
+                    .enumerate()
+                    .all(|(i, key)| !signers[..i].contains(key));
+                unique
+                    && signers.len() <= MAX_SIGNERS
+                    && *threshold > 0
+                    && *threshold as usize <= signers.len()
+            }
+        }
+    }
+}
+
+/// Lifecycle of an account, stored in the first byte of the header where
+/// the initialized flag was. Uninitialized and Active share the byte
+/// values of the flag, so the layout is unchanged
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq)]
+pub enum AccountLifecycle {
+    Uninitialized,
+    /// Content may be updated by the authority
+    Active,
+    /// Content is read only until the authority thaws the account
+    Frozen,
+    /// Unpacked from backlevel data, the next write migrates the account
+    PendingMigration,
+    /// Lamports were withdrawn, the account can not be used again
+    Closed,
+}
+
+// Not derived for the BPF toolchain, as with AccountAuthority
+#[allow(clippy::derivable_impls)]
+impl Default for AccountLifecycle {
+    fn default() -> Self {
+        AccountLifecycle::Uninitialized
+    }
+}
+
+/// Seed prefix of program derived accounts
+pub const ACCOUNT_SEED_PREFIX: &[u8] = b"account";
+
+/// Finds the program derived account address for the owner wallet and label
+pub fn find_account_address(program_id: &Pubkey, owner: &Pubkey, label: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[ACCOUNT_SEED_PREFIX, owner.as_ref(), label.as_bytes()],
+        program_id,
+    )
+}
+
 /// Maintains account data
 #[derive(BorshDeserialize, BorshSerialize, Debug, Default, PartialEq)]
 pub struct ProgramAccountState {
-    is_initialized: bool,
+    lifecycle: AccountLifecycle,
     data_version: u8,
+    write_sequence: u64,
+    authority: AccountAuthority,
+    bump_seed: Option<u8>,
     account_data: AccountContentCurrent,
 }
 
+/// Account layout for DATA_VERSION 4, before named fields were added
+#[derive(BorshDeserialize)]
+struct ProgramAccountStateV4 {
+    lifecycle: AccountLifecycle,
+    _data_version: u8,
+    write_sequence: u64,
+    authority: AccountAuthority,
+    bump_seed: Option<u8>,
+    account_data: AccountContentV1,
+}
+
+/// Account layout for DATA_VERSION 3, before the bump seed was added
+#[derive(BorshDeserialize)]
+struct ProgramAccountStateV3 {
+    is_initialized: bool,
+    _data_version: u8,
+    write_sequence: u64,
+    authority: AccountAuthority,
+    account_data: AccountContentV1,
+}
+
+/// Account layout for DATA_VERSION 2, before the write sequence was added
+#[derive(BorshDeserialize)]
+struct ProgramAccountStateV2 {
+    is_initialized: bool,
+    _data_version: u8,
+    authority: AccountAuthority,
+    account_data: AccountContentV1,
+}
+
+/// Account layout for DATA_VERSION 1, before the authority was added
+#[derive(BorshDeserialize)]
+struct ProgramAccountStateV1 {
+    is_initialized: bool,
+    _data_version: u8,
+    account_data: AccountContentV1,
+}
+
 impl ProgramAccountState {
     /// Signal initialized
     pub fn set_initialized(&mut self) {
-        self.is_initialized = true;
+        self.lifecycle = AccountLifecycle::Active;
     }
     /// Get the initialized flag
     pub fn initialized(&self) -> bool {
-        self.is_initialized
+        self.lifecycle != AccountLifecycle::Uninitialized
+    }
+    /// Get the lifecycle state
+    pub fn lifecycle(&self) -> AccountLifecycle {
+        self.lifecycle
     }
-    /// Gets the current data version
+    /// Move to another lifecycle state, the processor checks the transition
+    pub fn set_lifecycle(&mut self, lifecycle: AccountLifecycle) {
+        self.lifecycle = lifecycle;
+    }
+    /// Gets the data version the account is written at, the current data
+    /// version unless an older layout was requested with set_version
     pub fn version(&self) -> u8 {
         self.data_version
     }
+    /// Write the account at an older layout, from OLDEST_WRITABLE_VERSION,
+    /// until a scheduled migration activates
+    pub fn set_version(&mut self, data_version: u8) -> Result<(), ProgramError> {
+        if !(OLDEST_WRITABLE_VERSION..=DATA_VERSION).contains(&data_version) {
+            log_error!("Fail: Data version {} can not be written", data_version);
+            return Err(DataVersionError::UnsupportedVersion.into());
+        }
+        self.data_version = data_version;
+        Ok(())
+    }
+    /// Gets the number of times the account has been written
+    pub fn write_sequence(&self) -> u64 {
+        self.write_sequence
+    }
+    /// Get the authority permitted to mutate the account
+    pub fn authority(&self) -> &AccountAuthority {
+        &self.authority
+    }
+    /// Replace the authority permitted to mutate the account
+    pub fn set_authority(&mut self, authority: AccountAuthority) {
+        self.authority = authority;
+    }
+    /// Get the bump seed of a program derived account
+    pub fn bump_seed(&self) -> Option<u8> {
+        self.bump_seed
+    }
+    /// Record the bump seed of a program derived account
+    pub fn set_bump_seed(&mut self, bump_seed: u8) {
+        self.bump_seed = Some(bump_seed);
+    }
     /// Get the reference to content structure
     pub fn content(&self) -> &AccountContentCurrent {
         &self.account_data
@@ -54,15 +259,134 @@ impl ProgramAccountState {
     pub fn content_mut(&mut self) -> &mut AccountContentCurrent {
         &mut self.account_data
     }
+    /// Get a named field
+    pub fn field(&self, key: &str) -> Option<&FieldValue> {
+        self.account_data.fields.get(key)
+    }
+    /// Get a named u64 field, None if absent or of another type
+    pub fn get_u64(&self, key: &str) -> Option<u64> {
+        match self.field(key) {
+            Some(FieldValue::U64(value)) => Some(*value),
+            _ => None,
+        }
+    }
+    /// Get a named i64 field, None if absent or of another type
+    pub fn get_i64(&self, key: &str) -> Option<i64> {
+        match self.field(key) {
+            Some(FieldValue::I64(value)) => Some(*value),
+            _ => None,
+        }
+    }
+    /// Get a named bool field, None if absent or of another type
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.field(key) {
+            Some(FieldValue::Bool(value)) => Some(*value),
+            _ => None,
+        }
+    }
+    /// Get a named string field, None if absent or of another type
+    pub fn get_string(&self, key: &str) -> Option<&str> {
+        match self.field(key) {
+            Some(FieldValue::String(value)) => Some(value),
+            _ => None,
+        }
+    }
+    /// Get a named pubkey field, None if absent or of another type
+    pub fn get_pubkey(&self, key: &str) -> Option<&Pubkey> {
+        match self.field(key) {
+            Some(FieldValue::Pubkey(value)) => Some(value),
+            _ => None,
+        }
+    }
+    /// Insert or replace a named field, failing if the name, the value or
+    /// the account as a whole would exceed its limits
+    pub fn set_field(&mut self, key: String, value: FieldValue) -> Result<(), ProgramError> {
+        if key.is_empty() || key.len() > MAX_FIELD_KEY_LEN {
+            log_error!("Fail: Field name must be 1 to {} bytes", MAX_FIELD_KEY_LEN);
+            return Err(DataVersionError::FieldLimitExceeded.into());
+        }
+        if let FieldValue::String(value) = &value {
+            if value.len() > MAX_FIELD_STRING_LEN {
+                log_error!("Fail: Field string exceeds {} bytes", MAX_FIELD_STRING_LEN);
+                return Err(DataVersionError::FieldLimitExceeded.into());
+            }
+        }
+        let fields = &mut self.account_data.fields;
+        if !fields.contains_key(&key) && fields.len() >= MAX_FIELDS {
+            log_error!("Fail: Content already holds {} fields", MAX_FIELDS);
+            return Err(DataVersionError::FieldLimitExceeded.into());
+        }
+        let previous = fields.insert(key.clone(), value);
+        if self.try_to_vec()?.len() > ACCOUNT_STATE_SPACE {
+            log_error!("Fail: Field {} does not fit the account", key);
+            match previous {
+                Some(previous) => self.account_data.fields.insert(key, previous),
+                None => self.account_data.fields.remove(&key),
+            };
+            return Err(DataVersionError::FieldLimitExceeded.into());
+        }
+        Ok(())
+    }
+    /// Remove a named field, returning its value
+    pub fn remove_field(&mut self, key: &str) -> Option<FieldValue> {
+        self.account_data.fields.remove(key)
+    }
+    /// Unpack initialized state as `Pack::unpack` does, giving fields
+    /// that backlevel data lacks the `defaults`
+    pub fn unpack_with_defaults(
+        src: &[u8],
+        defaults: &FieldDefaults,
+    ) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(DataVersionError::WrongAccountKind.into());
+        }
+        if src[0] == 0 {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        if src[1] == DATA_VERSION {
+            Self::unpack_from_slice(src)
+        } else {
+            log_debug!("Processing backlevel data");
+            conversion_logic(src, defaults)
+        }
+    }
 }
 
 /// Declaration of the current data version.
-const DATA_VERSION: u8 = 1; // Adding string to content
-                            // Previous const DATA_VERSION: u8 = 0;
+pub const DATA_VERSION: u8 = 5; // Adding named fields to content
+                                // Previous const DATA_VERSION: u8 = 4; Adding bump seed to header
+                                // Previous const DATA_VERSION: u8 = 3; Adding write sequence to header
+                                // Previous const DATA_VERSION: u8 = 2; Adding authority to header
+                                // Previous const DATA_VERSION: u8 = 1; Adding string to content
+                                // Previous const DATA_VERSION: u8 = 0;
+/// Oldest data version the conversion logic can read
+pub const OLDEST_SUPPORTED_VERSION: u8 = 0;
+/// Oldest data version accounts can still be written at, see set_version
+pub const OLDEST_WRITABLE_VERSION: u8 = 4;
+/// Version of the program crate
+pub const PROGRAM_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Version details returned by the GetVersionInfo instruction
+#[derive(BorshDeserialize, BorshSerialize, Debug, PartialEq)]
+pub struct VersionInfo {
+    pub data_version: u8,
+    pub oldest_supported_version: u8,
+    pub program_version: String,
+}
+
+impl Default for VersionInfo {
+    fn default() -> Self {
+        VersionInfo {
+            data_version: DATA_VERSION,
+            oldest_supported_version: OLDEST_SUPPORTED_VERSION,
+            program_version: String::from(PROGRAM_VERSION),
+        }
+    }
+}
 
 /// Account allocated size
 const ACCOUNT_ALLOCATION_SIZE: usize = 1024;
-/// Initialized flag is 1st byte of data block
+/// Initialized flag (lifecycle) is 1st byte of data block
 const IS_INITIALIZED: usize = 1;
 /// Data version (current) is 2nd byte of data block
 const DATA_VERSION_ID: usize = 1;
@@ -72,18 +396,59 @@ const PREVIOUS_VERSION_DATA_SIZE: usize = mem::size_of::<AccountContentOld>();
 /// Total space occupied by previous account data
 const PREVIOUS_ACCOUNT_SPACE: usize = IS_INITIALIZED + DATA_VERSION_ID + PREVIOUS_VERSION_DATA_SIZE;
 
+/// Current write sequence size
+const CURRENT_WRITE_SEQUENCE_SIZE: usize = mem::size_of::<u64>();
+/// Current authority size
+const CURRENT_AUTHORITY_SIZE: usize = mem::size_of::<AccountAuthority>();
+/// Current bump seed size
+const CURRENT_BUMP_SEED_SIZE: usize = mem::size_of::<Option<u8>>();
 /// Current content data size
 const CURRENT_VERSION_DATA_SIZE: usize = mem::size_of::<AccountContentCurrent>();
 /// Total usage for data only
-const CURRENT_USED_SIZE: usize = IS_INITIALIZED + DATA_VERSION_ID + CURRENT_VERSION_DATA_SIZE;
+const CURRENT_USED_SIZE: usize = IS_INITIALIZED
+    + DATA_VERSION_ID
+    + CURRENT_WRITE_SEQUENCE_SIZE
+    + CURRENT_AUTHORITY_SIZE
+    + CURRENT_BUMP_SEED_SIZE
+    + CURRENT_VERSION_DATA_SIZE;
 /// How much of 1024 is used
 const CURRENT_UNUSED_SIZE: usize = ACCOUNT_ALLOCATION_SIZE - CURRENT_USED_SIZE;
 /// Current space used by header (initialized, data version and Content)
 pub const ACCOUNT_STATE_SPACE: usize = CURRENT_USED_SIZE + CURRENT_UNUSED_SIZE;
 
-/// Future data migration logic that converts prior state of data
-/// to current state of data
-fn conversion_logic(src: &[u8]) -> Result<ProgramAccountState, ProgramError> {
+/// Lifecycle of backlevel data, which only recorded the initialized flag
+fn backlevel_lifecycle(is_initialized: bool) -> AccountLifecycle {
+    if is_initialized {
+        AccountLifecycle::PendingMigration
+    } else {
+        AccountLifecycle::Uninitialized
+    }
+}
+
+/// Data migration logic that converts prior state of data
+/// to current state of data, dispatching on the stored data version
+fn conversion_logic(
+    src: &[u8],
+    defaults: &FieldDefaults,
+) -> Result<ProgramAccountState, ProgramError> {
+    match src[1] {
+        0 => conversion_from_v0(src, defaults),
+        1 => conversion_from_v1(src),
+        2 => conversion_from_v2(src),
+        3 => conversion_from_v3(src),
+        4 => conversion_from_v4(src),
+        version => {
+            log_error!("Unsupported data version {}", version);
+            Err(DataVersionError::UnsupportedVersion.into())
+        }
+    }
+}
+
+/// Converts DATA_VERSION 0 (somevalue only) to current state of data
+fn conversion_from_v0(
+    src: &[u8],
+    defaults: &FieldDefaults,
+) -> Result<ProgramAccountState, ProgramError> {
     let past = array_ref![src, 0, PREVIOUS_ACCOUNT_SPACE];
     let (initialized, _, account_space) = array_refs![
         past,
@@ -93,34 +458,170 @@ fn conversion_logic(src: &[u8]) -> Result<ProgramAccountState, ProgramError> {
     ];
     // Logic to uplift from previous version
     // GOES HERE
-    let old = try_from_slice_unchecked::<AccountContentOld>(account_space).unwrap();
-    // Default sets 'somevalue' to 0 and somestring to default ""
-    let mut new_content = AccountContentCurrent::default();
+    let old = try_from_slice_unchecked::<AccountContentOld>(account_space)
+        .map_err(|_| DataVersionError::MigrationFailed)?;
     // We copy the existing 'somevalue', the program instructions will read/update 'somestring' without fail
-    new_content.somevalue = old.somevalue;
+    // The config may give somestring a default other than ""
+    let new_content = AccountContentCurrent {
+        somevalue: old.somevalue,
+        somestring: defaults.somestring.clone(),
+        ..AccountContentCurrent::default()
+    };
 
     // Give back
     Ok(ProgramAccountState {
-        is_initialized: initialized[0] != 0u8,
+        lifecycle: backlevel_lifecycle(initialized[0] != 0u8),
         data_version: DATA_VERSION,
+        write_sequence: 0,
+        authority: AccountAuthority::None,
+        bump_seed: None,
         account_data: new_content,
     })
 }
+
+/// Converts DATA_VERSION 1 (no authority) to current state of data
+fn conversion_from_v1(src: &[u8]) -> Result<ProgramAccountState, ProgramError> {
+    let past = try_from_slice_unchecked::<ProgramAccountStateV1>(src)
+        .map_err(|_| DataVersionError::MigrationFailed)?;
+    // Accounts written before authorities existed stay open to anyone
+    Ok(ProgramAccountState {
+        lifecycle: backlevel_lifecycle(past.is_initialized),
+        data_version: DATA_VERSION,
+        write_sequence: 0,
+        authority: AccountAuthority::None,
+        bump_seed: None,
+        account_data: past.account_data.into(),
+    })
+}
+
+/// Converts DATA_VERSION 2 (no write sequence) to current state of data
+fn conversion_from_v2(src: &[u8]) -> Result<ProgramAccountState, ProgramError> {
+    let past = try_from_slice_unchecked::<ProgramAccountStateV2>(src)
+        .map_err(|_| DataVersionError::MigrationFailed)?;
+    // Sequence counting starts with the first write at the current version
+    Ok(ProgramAccountState {
+        lifecycle: backlevel_lifecycle(past.is_initialized),
+        data_version: DATA_VERSION,
+        write_sequence: 0,
+        authority: past.authority,
+        bump_seed: None,
+        account_data: past.account_data.into(),
+    })
+}
+
+/// Converts DATA_VERSION 3 (no bump seed) to current state of data
+fn conversion_from_v3(src: &[u8]) -> Result<ProgramAccountState, ProgramError> {
+    let past = try_from_slice_unchecked::<ProgramAccountStateV3>(src)
+        .map_err(|_| DataVersionError::MigrationFailed)?;
+    // Program derived accounts did not exist before DATA_VERSION 4
+    Ok(ProgramAccountState {
+        lifecycle: backlevel_lifecycle(past.is_initialized),
+        data_version: DATA_VERSION,
+        write_sequence: past.write_sequence,
+        authority: past.authority,
+        bump_seed: None,
+        account_data: past.account_data.into(),
+    })
+}
+
+/// Converts DATA_VERSION 4 (no named fields) to current state of data
+fn conversion_from_v4(src: &[u8]) -> Result<ProgramAccountState, ProgramError> {
+    let past = try_from_slice_unchecked::<ProgramAccountStateV4>(src)
+        .map_err(|_| DataVersionError::MigrationFailed)?;
+    // The lifecycle was recorded in full from DATA_VERSION 4
+    let lifecycle = match past.lifecycle {
+        AccountLifecycle::Active => AccountLifecycle::PendingMigration,
+        lifecycle => lifecycle,
+    };
+    Ok(ProgramAccountState {
+        lifecycle,
+        data_version: DATA_VERSION,
+        write_sequence: past.write_sequence,
+        authority: past.authority,
+        bump_seed: past.bump_seed,
+        account_data: past.account_data.into(),
+    })
+}
+
+impl ProgramAccountState {
+    /// Writes the layout of the data version
+    fn serialize_layout<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        if self.data_version == DATA_VERSION {
+            self.serialize(writer)
+        } else {
+            self.serialize_v4(writer)
+        }
+    }
+
+    /// Writes the layout of the data version to the data area, refusing
+    /// content that does not fit rather than truncating it
+    pub(crate) fn pack_checked(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        let mut layout = Vec::with_capacity(dst.len());
+        self.serialize_layout(&mut layout)?;
+        if layout.len() > dst.len() {
+            log_error!(
+                "Fail: Account content of {} bytes exceeds {}",
+                layout.len(),
+                dst.len()
+            );
+            return Err(DataVersionError::FieldLimitExceeded.into());
+        }
+        dst[..layout.len()].copy_from_slice(&layout);
+        Ok(())
+    }
+
+    /// Writes the DATA_VERSION 4 layout, which has no named fields
+    fn serialize_v4<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.lifecycle.serialize(writer)?;
+        self.data_version.serialize(writer)?;
+        self.write_sequence.serialize(writer)?;
+        self.authority.serialize(writer)?;
+        self.bump_seed.serialize(writer)?;
+        self.account_data.somevalue.serialize(writer)?;
+        self.account_data.somestring.serialize(writer)
+    }
+}
+
 impl Sealed for ProgramAccountState {}
 
 impl IsInitialized for ProgramAccountState {
     fn is_initialized(&self) -> bool {
-        self.is_initialized
+        self.initialized()
     }
 }
 
 impl Pack for ProgramAccountState {
     const LEN: usize = ACCOUNT_STATE_SPACE;
 
-    /// Store 'state' of account to its data area
+    /// Store 'state' of account, advancing the write sequence. Writing
+    /// stores the current data version, completing any pending migration
+    fn pack(mut src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        if dst.len() != Self::LEN {
+            return Err(DataVersionError::WrongAccountKind.into());
+        }
+        if src.lifecycle == AccountLifecycle::PendingMigration {
+            src.lifecycle = AccountLifecycle::Active;
+        }
+        if src.data_version < DATA_VERSION && !src.account_data.fields.is_empty() {
+            log_error!(
+                "Fail: Data version {} has no named fields",
+                src.data_version
+            );
+            return Err(DataVersionError::MigrationNotActive.into());
+        }
+        src.write_sequence = src
+            .write_sequence
+            .checked_add(1)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        src.pack_checked(dst)
+    }
+
+    /// Store 'state' of account to its data area, in the layout of its
+    /// data version. Panics when the layout exceeds the data area, which
+    /// `pack` refuses before writing
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        let mut bw = BufWriter::new(dst);
-        self.serialize(&mut bw).unwrap();
+        self.serialize_layout(&mut &mut dst[..])
+            .expect("account layout exceeds the data area");
     }
 
     /// Retrieve 'state' of account from account data area
@@ -130,17 +631,21 @@ impl Pack for ProgramAccountState {
         if initialized {
             // Version check
             if src[1] == DATA_VERSION {
-                msg!("Processing consistent version data");
-                Ok(try_from_slice_unchecked::<ProgramAccountState>(src).unwrap())
+                log_debug!("Processing consistent version data");
+                try_from_slice_unchecked::<ProgramAccountState>(src)
+                    .map_err(|_| DataVersionError::DeserializationFailure.into())
             } else {
-                msg!("Processing backlevel data");
-                conversion_logic(src)
+                log_debug!("Processing backlevel data");
+                conversion_logic(src, &FieldDefaults::default())
             }
         } else {
-            msg!("Processing pre-initialized data");
+            log_debug!("Processing pre-initialized data");
             Ok(ProgramAccountState {
-                is_initialized: false,
+                lifecycle: AccountLifecycle::Uninitialized,
                 data_version: DATA_VERSION,
+                write_sequence: 0,
+                authority: AccountAuthority::None,
+                bump_seed: None,
                 account_data: AccountContentCurrent::default(),
             })
         }
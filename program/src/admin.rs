@@ -0,0 +1,199 @@
+//! Admin instructions for emergency repairs, which only the program
+//! upgrade authority may run
+
+use crate::{
+    account_state::{AccountLifecycle, ProgramAccountState, DATA_VERSION, OLDEST_WRITABLE_VERSION},
+    config::{ConfigUpdate, ProgramConfig, ScheduledMigration},
+    error::DataVersionError,
+    events::ProgramEvent,
+    logging::{log_debug, log_error, log_info},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::Pack,
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+};
+
+/// Finds the ProgramData account the upgradeable loader keeps for the program
+pub fn find_program_data_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id()).0
+}
+
+/// Confirms the authority has signed and is the upgrade authority recorded
+/// in the ProgramData account of the program deployed at `program_id`
+pub fn check_upgrade_authority(
+    program_id: &Pubkey,
+    authority: &AccountInfo,
+    program_data: &AccountInfo,
+) -> ProgramResult {
+    if !authority.is_signer {
+        log_error!("Fail: Authority {} has not signed", authority.key);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *program_data.key != find_program_data_address(program_id)
+        || *program_data.owner != bpf_loader_upgradeable::id()
+    {
+        log_error!("Fail: Account {} is not the program data", program_data.key);
+        return Err(ProgramError::InvalidArgument);
+    }
+    let metadata_len = UpgradeableLoaderState::programdata_data_offset()
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    let program_data = program_data.data.borrow();
+    let state = program_data.get(..metadata_len).and_then(|metadata| {
+        limited_deserialize::<UpgradeableLoaderState>(metadata, metadata_len as u64).ok()
+    });
+    match state {
+        Some(UpgradeableLoaderState::ProgramData {
+            upgrade_authority_address: Some(address),
+            ..
+        }) if address == *authority.key => Ok(()),
+        _ => {
+            log_error!("Fail: {} is not the upgrade authority", authority.key);
+            Err(DataVersionError::NotUpgradeAuthority.into())
+        }
+    }
+}
+
+/// Migrates the programs account, which is the first in accounts, whatever
+/// its lifecycle and even while migrations are paused
+pub fn force_migrate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: &ProgramConfig,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let program_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let program_data = next_account_info(account_info_iter)?;
+    check_upgrade_authority(program_id, authority, program_data)?;
+    let mut account_data = program_account.data.borrow_mut();
+    let account_state =
+        ProgramAccountState::unpack_with_defaults(&account_data, &config.field_defaults)?;
+    if account_state.lifecycle() == AccountLifecycle::Closed {
+        log_error!("Fail: Account is closed");
+        return Err(DataVersionError::InvalidLifecycleState.into());
+    }
+    let stored_version = account_data[1];
+    if stored_version == account_state.version() {
+        log_debug!("Account already at data version {}", stored_version);
+        return Ok(());
+    }
+    log_info!(
+        "Force migrate {:?} account from data version {} to {}",
+        account_state.lifecycle(),
+        stored_version,
+        account_state.version()
+    );
+    let to_version = account_state.version();
+    // Serialize
+    ProgramAccountState::pack(account_state, &mut account_data)?;
+    ProgramEvent::AccountMigrated {
+        account: *program_account.key,
+        from_version: stored_version,
+        to_version,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Overwrites the lifecycle and data version bytes of the programs account,
+/// which decide how the rest of the account is read, leaving the remaining
+/// data untouched
+pub fn rewrite_header(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    lifecycle: AccountLifecycle,
+    data_version: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let program_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let program_data = next_account_info(account_info_iter)?;
+    check_upgrade_authority(program_id, authority, program_data)?;
+    if data_version > DATA_VERSION {
+        log_error!("Fail: Data version {} is unknown", data_version);
+        return Err(DataVersionError::UnsupportedVersion.into());
+    }
+    // Before DATA_VERSION 4 the first byte is the borsh bool initialized flag
+    if data_version < 4
+        && !matches!(
+            lifecycle,
+            AccountLifecycle::Uninitialized | AccountLifecycle::Active
+        )
+    {
+        log_error!(
+            "Fail: Data version {} can not record {:?}",
+            data_version,
+            lifecycle
+        );
+        return Err(DataVersionError::InvalidLifecycleState.into());
+    }
+    let mut account_data = program_account.data.borrow_mut();
+    if account_data.len() != ProgramAccountState::LEN {
+        return Err(DataVersionError::WrongAccountKind.into());
+    }
+    log_info!(
+        "Rewrite header as {:?} at data version {}",
+        lifecycle,
+        data_version
+    );
+    account_data[0] = lifecycle as u8;
+    account_data[1] = data_version;
+    Ok(())
+}
+
+/// Applies the changes to the config, which is the first in accounts
+pub fn set_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    update: ConfigUpdate,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let program_data = next_account_info(account_info_iter)?;
+    check_upgrade_authority(program_id, authority, program_data)?;
+    log_info!("Set config");
+    let mut config_data = config_account.data.borrow_mut();
+    let mut config = ProgramConfig::unpack(&config_data)?;
+    config.apply(update)?;
+    ProgramConfig::pack(config, &mut config_data)
+}
+
+/// Announces a data version upgrade that takes effect at the activation
+/// slot, which is the first in accounts. Before it accounts are written at
+/// the version preceding the target, which must still be writable
+pub fn schedule_migration(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    migration: ScheduledMigration,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let program_data = next_account_info(account_info_iter)?;
+    check_upgrade_authority(program_id, authority, program_data)?;
+    if migration.target_version <= OLDEST_WRITABLE_VERSION
+        || migration.target_version > DATA_VERSION
+    {
+        log_error!(
+            "Fail: Target version must be above {} and at most {}",
+            OLDEST_WRITABLE_VERSION,
+            DATA_VERSION
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+    log_info!(
+        "Schedule migration to data version {} at slot {}",
+        migration.target_version,
+        migration.activation_slot
+    );
+    let mut config_data = config_account.data.borrow_mut();
+    let mut config = ProgramConfig::unpack(&config_data)?;
+    config.scheduled_migration = Some(migration);
+    ProgramConfig::pack(config, &mut config_data)
+}
@@ -0,0 +1,116 @@
+//! Anchor compatible instruction and account discriminators, enabled
+//! with the `anchor-compat` feature
+
+use crate::{
+    account_state::{FieldDefaults, ProgramAccountState},
+    error::DataVersionError,
+    instruction::VersionProgramInstruction,
+};
+use borsh::BorshSerialize;
+use solana_program::{hash::hashv, program_error::ProgramError, program_pack::Pack};
+use std::io::Write;
+
+/// Length of Anchor instruction and account discriminators
+pub const DISCRIMINATOR_LEN: usize = 8;
+
+/// Anchor names of the VersionProgramInstruction variants, in variant order
+const INSTRUCTION_NAMES: &[&str] = &[
+    "initialize_account",
+    "set_u64_value",
+    "set_string",
+    "fail_instruction",
+    "set_authority",
+    "migrate_account",
+    "get_state",
+    "get_version_info",
+    "compare_and_set_u64",
+    "compare_and_set_string",
+    "batch",
+    "freeze_account",
+    "thaw_account",
+    "close_account",
+    "initialize_config",
+    "update_config",
+    "force_migrate",
+    "rewrite_header",
+    "set_config",
+    "add_u64",
+    "sub_u64",
+    "increment_u64",
+    "set_field",
+    "remove_field",
+    "set_u64_value_signed",
+    "set_string_signed",
+    "schedule_migration",
+];
+
+/// First 8 bytes of the sha256 of `namespace:name`, as Anchor derives them
+pub fn discriminator(namespace: &str, name: &str) -> [u8; DISCRIMINATOR_LEN] {
+    let mut discriminator = [0u8; DISCRIMINATOR_LEN];
+    discriminator.copy_from_slice(
+        &hashv(&[namespace.as_bytes(), b":", name.as_bytes()]).to_bytes()[..DISCRIMINATOR_LEN],
+    );
+    discriminator
+}
+
+/// Replaces a leading instruction sighash with the borsh variant index,
+/// None if the input does not start with a known sighash
+pub fn sighash_to_borsh(input: &[u8]) -> Option<Vec<u8>> {
+    let (sighash, args) = (input.get(..DISCRIMINATOR_LEN)?, &input[DISCRIMINATOR_LEN..]);
+    let index = INSTRUCTION_NAMES
+        .iter()
+        .position(|name| discriminator("global", name) == sighash)?;
+    let mut payload = vec![index as u8];
+    payload.extend_from_slice(args);
+    Some(payload)
+}
+
+/// Serializes the instruction with a sighash in place of the borsh
+/// variant index, as Anchor clients send it
+pub fn instruction_data(instruction: &VersionProgramInstruction) -> Result<Vec<u8>, ProgramError> {
+    let payload = instruction.try_to_vec()?;
+    let name = INSTRUCTION_NAMES
+        .get(payload[0] as usize)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let mut data = discriminator("global", name).to_vec();
+    data.extend_from_slice(&payload[1..]);
+    Ok(data)
+}
+
+impl ProgramAccountState {
+    /// Anchor account discriminator of the program account, for IDLs. It is
+    /// not stored: accounts keep the layout the program has always written
+    pub fn discriminator() -> [u8; DISCRIMINATOR_LEN] {
+        discriminator("account", "ProgramAccountState")
+    }
+
+    /// Writes the packed account as it is stored on chain, the counterpart
+    /// of `try_deserialize`
+    pub fn try_serialize<W: Write>(&self, writer: &mut W) -> Result<(), ProgramError> {
+        let mut packed = vec![0u8; Self::LEN];
+        self.pack_checked(&mut packed)?;
+        writer
+            .write_all(&packed)
+            .map_err(|_| ProgramError::AccountDataTooSmall)
+    }
+
+    /// Decodes the account data as stored on chain, converting backlevel
+    /// data, in place of Anchor's `AccountDeserialize`. Without a stored
+    /// discriminator, the account kind is checked by its length
+    pub fn try_deserialize(buf: &mut &[u8]) -> Result<Self, ProgramError> {
+        if buf.len() != Self::LEN {
+            return Err(DataVersionError::WrongAccountKind.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    /// Decodes the leading account data without checking its length
+    pub fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self, ProgramError> {
+        let data = buf
+            .get(..Self::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let account_state = Self::unpack_with_defaults(data, &FieldDefaults::default())?;
+        *buf = &buf[Self::LEN..];
+        Ok(account_state)
+    }
+}
@@ -0,0 +1,138 @@
+//! @brief config manages the program wide settings account
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    borsh::try_from_slice_unchecked,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+use std::io::BufWriter;
+
+use crate::{
+    account_state::{FieldDefaults, DATA_VERSION, OLDEST_SUPPORTED_VERSION},
+    logging::log_error,
+};
+
+/// Seed of the config program derived account
+pub const CONFIG_SEED: &[u8] = b"config";
+/// Config account allocated size
+pub const CONFIG_SPACE: usize = 256;
+/// Longest default string that fits the config account
+pub const MAX_DEFAULT_STRING_LEN: usize = 200;
+
+/// Finds the address of the singleton config account
+pub fn find_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], program_id)
+}
+
+/// Program wide settings, consulted before mutating or migrating any
+/// account. Until the admin creates the config account the default
+/// applies: migrations run, every supported version is readable and new
+/// fields take their type defaults
+#[derive(BorshDeserialize, BorshSerialize, Debug, Default, PartialEq)]
+pub struct ProgramConfig {
+    pub is_initialized: bool,
+    pub bump_seed: u8,
+    /// Key that must sign config updates
+    pub admin: Pubkey,
+    /// When set, backlevel accounts are neither migrated nor written
+    pub migrations_paused: bool,
+    /// Accounts stored at an older data version are refused
+    pub min_readable_version: u8,
+    /// Values given to fields added since an account was written
+    pub field_defaults: FieldDefaults,
+    /// Added after the config was introduced, absent in older configs
+    pub scheduled_migration: Option<ScheduledMigration>,
+}
+
+/// Data version upgrade announced ahead of time. Until the activation slot
+/// accounts keep being written at the version before the target. Accounts
+/// initialized meanwhile start at the current version
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq)]
+pub struct ScheduledMigration {
+    pub target_version: u8,
+    pub activation_slot: u64,
+}
+
+/// Changes to the config, fields left as None are unchanged
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Default, PartialEq)]
+pub struct ConfigUpdate {
+    pub admin: Option<Pubkey>,
+    pub migrations_paused: Option<bool>,
+    pub min_readable_version: Option<u8>,
+    pub field_defaults: Option<FieldDefaults>,
+}
+
+impl ProgramConfig {
+    /// Data version to write an account stored at `stored_version` at, where
+    /// `slot` gives the current slot when a migration is scheduled
+    pub fn write_version(
+        &self,
+        stored_version: u8,
+        slot: impl FnOnce() -> Result<u64, ProgramError>,
+    ) -> Result<u8, ProgramError> {
+        match self.scheduled_migration {
+            Some(migration) if slot()? < migration.activation_slot => {
+                Ok(stored_version.max(migration.target_version - 1))
+            }
+            _ => Ok(DATA_VERSION),
+        }
+    }
+
+    /// Applies the changes of `update`
+    pub fn apply(&mut self, update: ConfigUpdate) -> ProgramResult {
+        if let Some(admin) = update.admin {
+            self.admin = admin;
+        }
+        if let Some(migrations_paused) = update.migrations_paused {
+            self.migrations_paused = migrations_paused;
+        }
+        if let Some(min_readable_version) = update.min_readable_version {
+            // Above DATA_VERSION even migrated accounts could not be read
+            if !(OLDEST_SUPPORTED_VERSION..=DATA_VERSION).contains(&min_readable_version) {
+                log_error!(
+                    "Fail: Minimum readable version {} is not a supported version",
+                    min_readable_version
+                );
+                return Err(ProgramError::InvalidArgument);
+            }
+            self.min_readable_version = min_readable_version;
+        }
+        if let Some(field_defaults) = update.field_defaults {
+            if field_defaults.somestring.len() > MAX_DEFAULT_STRING_LEN {
+                log_error!(
+                    "Fail: Default string exceeds {} bytes",
+                    MAX_DEFAULT_STRING_LEN
+                );
+                return Err(ProgramError::InvalidArgument);
+            }
+            self.field_defaults = field_defaults;
+        }
+        Ok(())
+    }
+}
+
+impl Sealed for ProgramConfig {}
+
+impl IsInitialized for ProgramConfig {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for ProgramConfig {
+    const LEN: usize = CONFIG_SPACE;
+
+    /// Store config to its account data area
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut bw = BufWriter::new(dst);
+        self.serialize(&mut bw).unwrap();
+    }
+
+    /// Retrieve config from its account data area
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        try_from_slice_unchecked::<ProgramConfig>(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
@@ -0,0 +1,536 @@
+//! Typed wrappers through which other programs invoke the instructions.
+//! Each takes the signer seeds of the calling program derived accounts
+//! that sign, none to invoke without signing. The `program` account is
+//! invoked as given, so callers must validate its key is this program's
+//! id: an impostor program would receive the signatures of their accounts
+
+use crate::{
+    account_state::{
+        AccountAuthority, AccountContentCurrent, AccountLifecycle, FieldValue, VersionInfo,
+    },
+    config::ConfigUpdate,
+    error::DataVersionError,
+    instruction::{self, AccountUpdate, ValueBounds},
+};
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    program::{get_return_data, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Accounts of instructions that update a program account
+pub struct UpdateAccounts<'a, 'b> {
+    /// This program
+    pub program: &'b AccountInfo<'a>,
+    /// The program account
+    pub account: &'b AccountInfo<'a>,
+    /// The config account, see `find_config_address`
+    pub config: &'b AccountInfo<'a>,
+    /// The authority signers, none if the account has no authority
+    pub signers: &'b [AccountInfo<'a>],
+}
+
+impl<'a, 'b> UpdateAccounts<'a, 'b> {
+    fn signer_keys(&self) -> Vec<&Pubkey> {
+        self.signers.iter().map(|signer| signer.key).collect()
+    }
+
+    fn infos(&self) -> Vec<&'b AccountInfo<'a>> {
+        let mut infos = vec![self.account, self.config];
+        infos.extend(self.signers);
+        infos
+    }
+}
+
+/// Accounts creating a program derived account, see `find_account_address`
+pub struct DerivedAccounts<'a, 'b> {
+    pub program: &'b AccountInfo<'a>,
+    pub account: &'b AccountInfo<'a>,
+    pub owner: &'b AccountInfo<'a>,
+    pub payer: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+}
+
+/// Accounts identifying the program upgrade authority
+pub struct UpgradeAuthorityAccounts<'a, 'b> {
+    pub program: &'b AccountInfo<'a>,
+    pub authority: &'b AccountInfo<'a>,
+    /// The program data, see `find_program_data_address`
+    pub program_data: &'b AccountInfo<'a>,
+}
+
+/// Invokes the instruction with the accounts and this program
+fn invoke_instruction<'a>(
+    instruction: &Instruction,
+    program: &AccountInfo<'a>,
+    accounts: &[&AccountInfo<'a>],
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let mut infos: Vec<AccountInfo<'a>> = accounts.iter().map(|&info| info.clone()).collect();
+    infos.push(program.clone());
+    invoke_signed(instruction, &infos, signer_seeds)
+}
+
+/// Decodes the return data the program set
+fn return_data<T: BorshDeserialize>(program_id: &Pubkey) -> Result<T, ProgramError> {
+    match get_return_data() {
+        Some((returned_by, data)) if returned_by == *program_id => {
+            T::try_from_slice(&data).map_err(|_| DataVersionError::DeserializationFailure.into())
+        }
+        _ => Err(DataVersionError::DeserializationFailure.into()),
+    }
+}
+
+/// Invokes an instruction updating a program account
+fn invoke_update(
+    accounts: &UpdateAccounts,
+    instruction: Instruction,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_instruction(
+        &instruction,
+        accounts.program,
+        &accounts.infos(),
+        signer_seeds,
+    )
+}
+
+/// Invokes `InitializeAccount` for an existing account
+pub fn initialize_account<'a>(
+    program: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    somevalue: u64,
+    somestring: String,
+    authority: Option<AccountAuthority>,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction =
+        instruction::initialize_account(program.key, account.key, somevalue, somestring, authority);
+    invoke_instruction(&instruction, program, &[account], signer_seeds)
+}
+
+/// Invokes `InitializeAccount`, creating the program derived account
+pub fn initialize_derived_account(
+    accounts: &DerivedAccounts,
+    label: String,
+    somevalue: u64,
+    somestring: String,
+    authority: Option<AccountAuthority>,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::initialize_derived_account(
+        accounts.program.key,
+        accounts.owner.key,
+        accounts.payer.key,
+        label,
+        somevalue,
+        somestring,
+        authority,
+    );
+    invoke_instruction(
+        &instruction,
+        accounts.program,
+        &[
+            accounts.account,
+            accounts.owner,
+            accounts.payer,
+            accounts.system_program,
+        ],
+        signer_seeds,
+    )
+}
+
+/// Invokes `SetU64Value`
+pub fn set_u64_value(
+    accounts: &UpdateAccounts,
+    value: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::set_u64_value(
+        accounts.program.key,
+        accounts.account.key,
+        &accounts.signer_keys(),
+        value,
+    );
+    invoke_update(accounts, instruction, signer_seeds)
+}
+
+/// Invokes `SetString`
+pub fn set_string_value(
+    accounts: &UpdateAccounts,
+    value: String,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::set_string_value(
+        accounts.program.key,
+        accounts.account.key,
+        &accounts.signer_keys(),
+        value,
+    );
+    invoke_update(accounts, instruction, signer_seeds)
+}
+
+/// Invokes `SetAuthority`
+pub fn set_authority(
+    accounts: &UpdateAccounts,
+    authority: AccountAuthority,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::set_authority(
+        accounts.program.key,
+        accounts.account.key,
+        &accounts.signer_keys(),
+        authority,
+    );
+    invoke_update(accounts, instruction, signer_seeds)
+}
+
+/// Invokes `MigrateAccount`
+pub fn migrate_account(accounts: &UpdateAccounts, signer_seeds: &[&[&[u8]]]) -> ProgramResult {
+    let instruction = instruction::migrate_account(
+        accounts.program.key,
+        accounts.account.key,
+        &accounts.signer_keys(),
+    );
+    invoke_update(accounts, instruction, signer_seeds)
+}
+
+/// Invokes `GetState`, returning the content converted to the current
+/// data version under the config, see `find_config_address`
+pub fn get_state<'a>(
+    program: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    config: &AccountInfo<'a>,
+) -> Result<AccountContentCurrent, ProgramError> {
+    let instruction = instruction::get_state(program.key, account.key);
+    invoke_instruction(&instruction, program, &[account, config], &[])?;
+    return_data(program.key)
+}
+
+/// Invokes `GetVersionInfo`, returning the data versions the program supports
+pub fn get_version_info(program: &AccountInfo) -> Result<VersionInfo, ProgramError> {
+    let instruction = instruction::get_version_info(program.key);
+    invoke_instruction(&instruction, program, &[], &[])?;
+    return_data(program.key)
+}
+
+/// Invokes `CompareAndSetU64`
+pub fn compare_and_set_u64(
+    accounts: &UpdateAccounts,
+    expected_seq: u64,
+    value: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::compare_and_set_u64(
+        accounts.program.key,
+        accounts.account.key,
+        &accounts.signer_keys(),
+        expected_seq,
+        value,
+    );
+    invoke_update(accounts, instruction, signer_seeds)
+}
+
+/// Invokes `CompareAndSetString`
+pub fn compare_and_set_string(
+    accounts: &UpdateAccounts,
+    expected_seq: u64,
+    value: String,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::compare_and_set_string(
+        accounts.program.key,
+        accounts.account.key,
+        &accounts.signer_keys(),
+        expected_seq,
+        value,
+    );
+    invoke_update(accounts, instruction, signer_seeds)
+}
+
+/// Invokes `Batch`
+pub fn batch(
+    accounts: &UpdateAccounts,
+    updates: Vec<AccountUpdate>,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::batch(
+        accounts.program.key,
+        accounts.account.key,
+        &accounts.signer_keys(),
+        updates,
+    );
+    invoke_update(accounts, instruction, signer_seeds)
+}
+
+/// Invokes `FreezeAccount`
+pub fn freeze_account(accounts: &UpdateAccounts, signer_seeds: &[&[&[u8]]]) -> ProgramResult {
+    let instruction = instruction::freeze_account(
+        accounts.program.key,
+        accounts.account.key,
+        &accounts.signer_keys(),
+    );
+    invoke_update(accounts, instruction, signer_seeds)
+}
+
+/// Invokes `ThawAccount`
+pub fn thaw_account(accounts: &UpdateAccounts, signer_seeds: &[&[&[u8]]]) -> ProgramResult {
+    let instruction = instruction::thaw_account(
+        accounts.program.key,
+        accounts.account.key,
+        &accounts.signer_keys(),
+    );
+    invoke_update(accounts, instruction, signer_seeds)
+}
+
+/// Invokes `CloseAccount`, withdrawing the lamports to the destination
+pub fn close_account<'a>(
+    accounts: &UpdateAccounts<'a, '_>,
+    destination: &AccountInfo<'a>,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::close_account(
+        accounts.program.key,
+        accounts.account.key,
+        &accounts.signer_keys(),
+        destination.key,
+    );
+    let mut infos = accounts.infos();
+    infos.push(destination);
+    invoke_instruction(&instruction, accounts.program, &infos, signer_seeds)
+}
+
+/// Invokes `InitializeConfig`, recording the upgrade authority as admin
+pub fn initialize_config<'a>(
+    program: &AccountInfo<'a>,
+    config: &AccountInfo<'a>,
+    admin: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    program_data: &AccountInfo<'a>,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::initialize_config(program.key, admin.key, payer.key);
+    invoke_instruction(
+        &instruction,
+        program,
+        &[config, admin, payer, system_program, program_data],
+        signer_seeds,
+    )
+}
+
+/// Invokes `UpdateConfig`, signed by the admin
+pub fn update_config<'a>(
+    program: &AccountInfo<'a>,
+    config: &AccountInfo<'a>,
+    admin: &AccountInfo<'a>,
+    update: ConfigUpdate,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::update_config(program.key, admin.key, update);
+    invoke_instruction(&instruction, program, &[config, admin], signer_seeds)
+}
+
+/// Invokes `ForceMigrate`
+pub fn force_migrate<'a>(
+    accounts: &UpgradeAuthorityAccounts<'a, '_>,
+    account: &AccountInfo<'a>,
+    config: &AccountInfo<'a>,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction =
+        instruction::force_migrate(accounts.program.key, account.key, accounts.authority.key);
+    invoke_instruction(
+        &instruction,
+        accounts.program,
+        &[account, config, accounts.authority, accounts.program_data],
+        signer_seeds,
+    )
+}
+
+/// Invokes `RewriteHeader`
+pub fn rewrite_header<'a>(
+    accounts: &UpgradeAuthorityAccounts<'a, '_>,
+    account: &AccountInfo<'a>,
+    lifecycle: AccountLifecycle,
+    data_version: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::rewrite_header(
+        accounts.program.key,
+        account.key,
+        accounts.authority.key,
+        lifecycle,
+        data_version,
+    );
+    invoke_instruction(
+        &instruction,
+        accounts.program,
+        &[account, accounts.authority, accounts.program_data],
+        signer_seeds,
+    )
+}
+
+/// Invokes `SetConfig`
+pub fn set_config<'a>(
+    accounts: &UpgradeAuthorityAccounts<'a, '_>,
+    config: &AccountInfo<'a>,
+    update: ConfigUpdate,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::set_config(accounts.program.key, accounts.authority.key, update);
+    invoke_instruction(
+        &instruction,
+        accounts.program,
+        &[config, accounts.authority, accounts.program_data],
+        signer_seeds,
+    )
+}
+
+/// Invokes `AddU64`, returning the new value
+pub fn add_u64(
+    accounts: &UpdateAccounts,
+    amount: u64,
+    bounds: ValueBounds,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64, ProgramError> {
+    let instruction = instruction::add_u64(
+        accounts.program.key,
+        accounts.account.key,
+        &accounts.signer_keys(),
+        amount,
+        bounds,
+    );
+    invoke_update(accounts, instruction, signer_seeds)?;
+    return_data(accounts.program.key)
+}
+
+/// Invokes `SubU64`, returning the new value
+pub fn sub_u64(
+    accounts: &UpdateAccounts,
+    amount: u64,
+    bounds: ValueBounds,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64, ProgramError> {
+    let instruction = instruction::sub_u64(
+        accounts.program.key,
+        accounts.account.key,
+        &accounts.signer_keys(),
+        amount,
+        bounds,
+    );
+    invoke_update(accounts, instruction, signer_seeds)?;
+    return_data(accounts.program.key)
+}
+
+/// Invokes `IncrementU64`, returning the new value
+pub fn increment_u64(
+    accounts: &UpdateAccounts,
+    bounds: ValueBounds,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64, ProgramError> {
+    let instruction = instruction::increment_u64(
+        accounts.program.key,
+        accounts.account.key,
+        &accounts.signer_keys(),
+        bounds,
+    );
+    invoke_update(accounts, instruction, signer_seeds)?;
+    return_data(accounts.program.key)
+}
+
+/// Invokes `SetField`
+pub fn set_field(
+    accounts: &UpdateAccounts,
+    key: String,
+    value: FieldValue,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::set_field(
+        accounts.program.key,
+        accounts.account.key,
+        &accounts.signer_keys(),
+        key,
+        value,
+    );
+    invoke_update(accounts, instruction, signer_seeds)
+}
+
+/// Invokes `RemoveField`
+pub fn remove_field(
+    accounts: &UpdateAccounts,
+    key: String,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::remove_field(
+        accounts.program.key,
+        accounts.account.key,
+        &accounts.signer_keys(),
+        key,
+    );
+    invoke_update(accounts, instruction, signer_seeds)
+}
+
+/// Invokes `SetU64ValueSigned`, which must follow the Ed25519 program
+/// instruction with the authority signature in the transaction
+pub fn set_u64_value_signed<'a>(
+    program: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    config: &AccountInfo<'a>,
+    instructions_sysvar: &AccountInfo<'a>,
+    value: u64,
+    sequence: u64,
+) -> ProgramResult {
+    let instruction = instruction::set_u64_value_signed(program.key, account.key, value, sequence);
+    invoke_instruction(
+        &instruction,
+        program,
+        &[account, config, instructions_sysvar],
+        &[],
+    )
+}
+
+/// Invokes `SetStringSigned`, which must follow the Ed25519 program
+/// instruction with the authority signature in the transaction
+pub fn set_string_signed<'a>(
+    program: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    config: &AccountInfo<'a>,
+    instructions_sysvar: &AccountInfo<'a>,
+    value: String,
+    sequence: u64,
+) -> ProgramResult {
+    let instruction = instruction::set_string_signed(program.key, account.key, value, sequence);
+    invoke_instruction(
+        &instruction,
+        program,
+        &[account, config, instructions_sysvar],
+        &[],
+    )
+}
+
+/// Invokes `ScheduleMigration`
+pub fn schedule_migration<'a>(
+    accounts: &UpgradeAuthorityAccounts<'a, '_>,
+    config: &AccountInfo<'a>,
+    target_version: u8,
+    activation_slot: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::schedule_migration(
+        accounts.program.key,
+        accounts.authority.key,
+        target_version,
+        activation_slot,
+    );
+    invoke_instruction(
+        &instruction,
+        accounts.program,
+        &[config, accounts.authority, accounts.program_data],
+        signer_seeds,
+    )
+}
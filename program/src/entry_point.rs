@@ -1,13 +1,15 @@
 //! entry point for instruction execution
 
 use solana_program::{
-    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult,
-    program_error::PrintProgramError, pubkey::Pubkey,
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::PrintProgramError,
+    pubkey::Pubkey,
 };
 
-use crate::{error::DataVersionError, processor::process};
+use crate::{error::DataVersionError, logging, processor::process};
 
-entrypoint!(process_instruction);
+// Programs calling this one through CPI build it without the entrypoint
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
 pub fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -15,7 +17,9 @@ pub fn process_instruction(
 ) -> ProgramResult {
     if let Err(error) = process(program_id, accounts, instruction_data) {
         // catch the error so we can print it
-        error.print::<DataVersionError>();
+        if logging::ERROR_ENABLED {
+            error.print::<DataVersionError>();
+        }
         return Err(error);
     }
     Ok(())
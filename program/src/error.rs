@@ -1,19 +1,46 @@
 //! Custom error enum
 
+use crate::logging::log_error;
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 use solana_program::{
     decode_error::DecodeError,
+    instruction::InstructionError,
     program_error::{PrintProgramError, ProgramError},
 };
+#[cfg(not(target_arch = "bpf"))]
+use solana_sdk::transaction::TransactionError;
 use std::fmt;
 use thiserror::Error;
 
+/// Errors of the program, returned as `ProgramError::Custom` with the
+/// variant code. Codes are stable: new variants take the next code and
+/// the codes of removed variants are not reused
 #[derive(Clone, Debug, Eq, Error, FromPrimitive, PartialEq)]
 pub enum DataVersionError {
-    InvalidInstruction,
-    DeserializationFailure,
-    AlreadyInitializedState,
+    InvalidInstruction = 0,
+    DeserializationFailure = 1,
+    AlreadyInitializedState = 2,
+    SequenceMismatch = 3,
+    InvalidLifecycleState = 4,
+    AuthorityRequired = 5,
+    MigrationsPaused = 6,
+    VersionBelowMinimum = 7,
+    NotUpgradeAuthority = 8,
+    ArithmeticOverflow = 9,
+    ValueOutOfBounds = 10,
+    FieldLimitExceeded = 11,
+    FieldNotFound = 12,
+    MigrationNotActive = 13,
+    AccountNotWritable = 14,
+    UnexpectedAccount = 15,
+    AccountTooSmall = 16,
+    AccountNotRentExempt = 17,
+    UnsupportedVersion = 18,
+    WrongAccountKind = 19,
+    Unauthorized = 20,
+    AccountFrozen = 21,
+    MigrationFailed = 22,
 }
 
 impl From<DataVersionError> for ProgramError {
@@ -21,6 +48,27 @@ impl From<DataVersionError> for ProgramError {
         ProgramError::Custom(e as u32)
     }
 }
+impl DataVersionError {
+    /// Decodes the custom code of an instruction error
+    pub fn from_instruction_error(error: &InstructionError) -> Option<Self> {
+        match error {
+            InstructionError::Custom(code) => Self::from_u32(*code),
+            _ => None,
+        }
+    }
+
+    /// Decodes the error of a transaction that failed in an instruction of
+    /// this program, as BanksClient and RpcClient report it. Custom codes
+    /// of other programs decode too, so check the failing instruction
+    #[cfg(not(target_arch = "bpf"))]
+    pub fn from_transaction_error(error: &TransactionError) -> Option<Self> {
+        match error {
+            TransactionError::InstructionError(_, error) => Self::from_instruction_error(error),
+            _ => None,
+        }
+    }
+}
+
 impl<T> DecodeError<T> for DataVersionError {
     fn type_of() -> &'static str {
         "DataVersionError"
@@ -35,6 +83,42 @@ impl fmt::Display for DataVersionError {
                 f.write_str("Error Deserializing input data")
             }
             DataVersionError::AlreadyInitializedState => f.write_str("Account already initialized"),
+            DataVersionError::SequenceMismatch => {
+                f.write_str("Account written since expected sequence")
+            }
+            DataVersionError::InvalidLifecycleState => {
+                f.write_str("Operation not permitted in account lifecycle state")
+            }
+            DataVersionError::AuthorityRequired => f.write_str("Account has no authority"),
+            DataVersionError::MigrationsPaused => f.write_str("Migrations are paused"),
+            DataVersionError::VersionBelowMinimum => {
+                f.write_str("Account data version below minimum readable")
+            }
+            DataVersionError::NotUpgradeAuthority => {
+                f.write_str("Signer is not the program upgrade authority")
+            }
+            DataVersionError::ArithmeticOverflow => f.write_str("Arithmetic overflow"),
+            DataVersionError::ValueOutOfBounds => f.write_str("Value out of bounds"),
+            DataVersionError::FieldLimitExceeded => f.write_str("Field exceeds content limits"),
+            DataVersionError::FieldNotFound => f.write_str("Field not found"),
+            DataVersionError::MigrationNotActive => {
+                f.write_str("Scheduled migration not yet active")
+            }
+            DataVersionError::AccountNotWritable => f.write_str("Account is not writable"),
+            DataVersionError::UnexpectedAccount => f.write_str("Unexpected account"),
+            DataVersionError::AccountTooSmall => {
+                f.write_str("Account too small for the current data version")
+            }
+            DataVersionError::AccountNotRentExempt => f.write_str("Account is not rent exempt"),
+            DataVersionError::UnsupportedVersion => f.write_str("Unsupported data version"),
+            DataVersionError::WrongAccountKind => {
+                f.write_str("Account is not of the expected kind")
+            }
+            DataVersionError::Unauthorized => f.write_str("Authority has not signed"),
+            DataVersionError::AccountFrozen => f.write_str("Account is frozen"),
+            DataVersionError::MigrationFailed => {
+                f.write_str("Backlevel data could not be migrated")
+            }
         }
     }
 }
@@ -44,10 +128,6 @@ impl PrintProgramError for DataVersionError {
     where
         E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
     {
-        match self {
-            DataVersionError::InvalidInstruction => println!("Error: Invalid instruction"),
-            DataVersionError::DeserializationFailure => println!("Error Deserializing input data"),
-            DataVersionError::AlreadyInitializedState => println!("Account already initialized"),
-        }
+        log_error!("{}", self);
     }
 }
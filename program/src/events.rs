@@ -0,0 +1,138 @@
+//! Borsh encoded events the program logs with sol_log_data on state
+//! changes, and the host side parser that reads them back from
+//! transaction logs
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{log::sol_log_data, pubkey::Pubkey};
+#[cfg(not(target_arch = "bpf"))]
+use std::str::FromStr;
+
+/// First data field of every event log, telling them apart from data
+/// logged by other programs
+pub const EVENT_TAG: &[u8] = b"dv-event";
+
+/// State change of a program account
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq)]
+pub enum ProgramEvent {
+    /// Account initialized at `data_version`
+    AccountInitialized { account: Pubkey, data_version: u8 },
+    /// Account content updated, identified by the write sequence and data
+    /// version it was written at. The values are not logged, reading them
+    /// takes the account itself
+    ContentSet {
+        account: Pubkey,
+        data_version: u8,
+        write_sequence: u64,
+    },
+    /// Account rewritten from one data version to another
+    AccountMigrated {
+        account: Pubkey,
+        from_version: u8,
+        to_version: u8,
+    },
+    /// Account data length changed, including allocation on creation
+    AccountResized {
+        account: Pubkey,
+        from_len: u64,
+        to_len: u64,
+    },
+    /// Account closed and its lamports withdrawn to the destination
+    AccountClosed {
+        account: Pubkey,
+        destination: Pubkey,
+        lamports: u64,
+    },
+}
+
+impl ProgramEvent {
+    /// Logs the event as the tag and the borsh encoded event, unless
+    /// logs are off
+    pub fn emit(&self) {
+        if !crate::logging::EVENTS_ENABLED {
+            return;
+        }
+        if let Ok(data) = self.try_to_vec() {
+            sol_log_data(&[EVENT_TAG, &data]);
+        }
+    }
+
+    /// Decodes the data fields of one sol_log_data call, None if they
+    /// are not an event
+    pub fn from_log_data(fields: &[&[u8]]) -> Option<Self> {
+        match fields {
+            [tag, data] if *tag == EVENT_TAG => Self::try_from_slice(data).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Prefix the runtime gives sol_log_data lines in transaction logs
+#[cfg(not(target_arch = "bpf"))]
+const DATA_LOG_PREFIX: &str = "Program data: ";
+
+/// Prefixes of the runtime log lines that carry program output, which
+/// never report an invocation however they continue
+#[cfg(not(target_arch = "bpf"))]
+const OUTPUT_LOG_PREFIXES: &[&str] = &["Program log: ", DATA_LOG_PREFIX, "Program return: "];
+
+/// Invocation status the runtime logs for a program
+#[cfg(not(target_arch = "bpf"))]
+enum Invocation<'a> {
+    /// `Program <id> invoke [<depth>]`
+    Invoke(&'a str),
+    /// `Program <id> success` or `Program <id> failed: <error>`
+    Return,
+}
+
+/// Parses a runtime invocation line, None for any other line
+#[cfg(not(target_arch = "bpf"))]
+fn parse_invocation(log: &str) -> Option<Invocation<'_>> {
+    let (id, status) = log.strip_prefix("Program ")?.split_once(' ')?;
+    Pubkey::from_str(id).ok()?;
+    if status == "success" || status.starts_with("failed: ") {
+        return Some(Invocation::Return);
+    }
+    let depth = status.strip_prefix("invoke [")?.strip_suffix(']')?;
+    depth.parse::<usize>().ok()?;
+    Some(Invocation::Invoke(id))
+}
+
+/// Events logged by `program_id` in the log messages of a transaction.
+/// Invocations are tracked so data logged by other programs, including
+/// ones this program calls, is skipped. Only the exact runtime formats
+/// count, so programs cannot spoof them with the output they log
+#[cfg(not(target_arch = "bpf"))]
+pub fn parse_logs<S: AsRef<str>>(program_id: &Pubkey, logs: &[S]) -> Vec<ProgramEvent> {
+    let program = program_id.to_string();
+    let mut invoked = Vec::new();
+    let mut events = Vec::new();
+    for log in logs.iter().map(AsRef::as_ref) {
+        if let Some(data) = log.strip_prefix(DATA_LOG_PREFIX) {
+            if invoked.last() == Some(&true) {
+                let fields = data
+                    .split(' ')
+                    .map(base64::decode)
+                    .collect::<Result<Vec<_>, _>>();
+                if let Ok(fields) = fields {
+                    let fields = fields.iter().map(Vec::as_slice).collect::<Vec<_>>();
+                    events.extend(ProgramEvent::from_log_data(&fields));
+                }
+            }
+            continue;
+        }
+        if OUTPUT_LOG_PREFIXES
+            .iter()
+            .any(|prefix| log.starts_with(prefix))
+        {
+            continue;
+        }
+        match parse_invocation(log) {
+            Some(Invocation::Invoke(id)) => invoked.push(id == program),
+            Some(Invocation::Return) => {
+                invoked.pop();
+            }
+            None => (),
+        }
+    }
+    events
+}
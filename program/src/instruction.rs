@@ -1,31 +1,980 @@
 //! instruction Contains the main VersionProgramInstruction enum
 
 use {
-    crate::error::DataVersionError,
+    crate::{
+        account_state::{find_account_address, AccountAuthority, AccountLifecycle, FieldValue},
+        admin::find_program_data_address,
+        config::{find_config_address, ConfigUpdate},
+        error::DataVersionError,
+        logging::log_error,
+    },
     borsh::{BorshDeserialize, BorshSerialize},
-    solana_program::{borsh::try_from_slice_unchecked, msg, program_error::ProgramError},
+    solana_program::{
+        borsh::try_from_slice_unchecked,
+        instruction::{AccountMeta, Instruction},
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program, sysvar,
+    },
 };
 
+/// Maximum number of updates in a Batch instruction
+pub const MAX_BATCH_LEN: usize = 8;
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Default, PartialEq)]
+/// Initial content of an account and, for a program derived account, the
+/// label its address is derived from together with the owner wallet
+pub struct InitializeAccountArgs {
+    pub somevalue: u64,
+    pub somestring: String,
+    pub label: Option<String>,
+    /// Authority set in the same instruction, so no one else can take the
+    /// account over before the owner sets it
+    pub authority: Option<AccountAuthority>,
+}
+
+#[derive(BorshDeserialize)]
+/// InitializeAccount arguments from clients predating the initial authority
+struct InitializeAccountArgsV4 {
+    somevalue: u64,
+    somestring: String,
+    label: Option<String>,
+}
+
+impl From<InitializeAccountArgsV4> for InitializeAccountArgs {
+    fn from(args: InitializeAccountArgsV4) -> Self {
+        InitializeAccountArgs {
+            somevalue: args.somevalue,
+            somestring: args.somestring,
+            label: args.label,
+            authority: None,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, Default, PartialEq)]
+/// Inclusive limits on the result of arithmetic on somevalue, None is unbounded
+pub struct ValueBounds {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq)]
+/// Account updates that may be combined in a Batch instruction. Batches
+/// hold these rather than instructions so they cannot be nested
+pub enum AccountUpdate {
+    SetU64Value(u64),
+    SetString(String),
+    SetAuthority(AccountAuthority),
+    CompareAndSetU64 { expected_seq: u64, value: u64 },
+    CompareAndSetString { expected_seq: u64, value: String },
+    AddU64 { amount: u64, bounds: ValueBounds },
+    SubU64 { amount: u64, bounds: ValueBounds },
+    SetField { key: String, value: FieldValue },
+    RemoveField(String),
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Debug, PartialEq)]
 /// All custom program instructions
 pub enum VersionProgramInstruction {
-    InitializeAccount,
+    InitializeAccount(InitializeAccountArgs), // Arguments added with data version 4
     SetU64Value(u64),
     SetString(String), // Added with data version change
     FailInstruction,
+    SetAuthority(AccountAuthority), // Added with data version 2
+    MigrateAccount,
+    GetState,
+    GetVersionInfo,
+    CompareAndSetU64 {
+        expected_seq: u64,
+        value: u64,
+    }, // Added with data version 3
+    CompareAndSetString {
+        expected_seq: u64,
+        value: String,
+    },
+    /// Account updates applied in order and written once, all or nothing
+    Batch(Vec<AccountUpdate>),
+    /// Makes the account read only, signed by the authority
+    FreezeAccount,
+    /// Makes a frozen account writable again, signed by the authority
+    ThawAccount,
+    /// Withdraws all lamports and marks the account closed, signed by the authority
+    CloseAccount,
+    /// Creates the config account, recording the upgrade authority as admin
+    InitializeConfig,
+    /// Changes the config, signed by the admin
+    UpdateConfig(ConfigUpdate),
+    /// Migrates an account whatever its lifecycle, signed by the upgrade authority
+    ForceMigrate,
+    /// Overwrites the header bytes of a corrupted account, signed by the
+    /// upgrade authority
+    RewriteHeader {
+        lifecycle: AccountLifecycle,
+        data_version: u8,
+    },
+    /// Changes the config, signed by the upgrade authority
+    SetConfig(ConfigUpdate),
+    /// Arithmetic on somevalue, returning the new value
+    AddU64 {
+        amount: u64,
+        bounds: ValueBounds,
+    },
+    SubU64 {
+        amount: u64,
+        bounds: ValueBounds,
+    },
+    IncrementU64(ValueBounds),
+    /// Inserts or replaces a named field, added with data version 5
+    SetField {
+        key: String,
+        value: FieldValue,
+    },
+    RemoveField(String),
+    /// Sets a value the authority signed off-chain, see `SignedUpdate`
+    SetU64ValueSigned {
+        value: u64,
+        sequence: u64,
+    },
+    SetStringSigned {
+        value: String,
+        sequence: u64,
+    },
+    /// Upgrades accounts to the target version from the activation slot,
+    /// signed by the upgrade authority
+    ScheduleMigration {
+        target_version: u8,
+        activation_slot: u64,
+    },
 }
 
 impl VersionProgramInstruction {
     /// Unpack inbound buffer to associated Instruction
     /// The expected format for input is a Borsh serialized vector
     pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        let payload = try_from_slice_unchecked::<VersionProgramInstruction>(input).unwrap();
-        // let payload = VersionProgramInstruction::try_from_slice(input).unwrap();
+        // InitializeAccount from clients predating its arguments
+        if input == [0] {
+            return Ok(VersionProgramInstruction::InitializeAccount(
+                InitializeAccountArgs {
+                    somevalue: 1,
+                    ..InitializeAccountArgs::default()
+                },
+            ));
+        }
+        // Anchor clients prefix the arguments with a sighash of the instruction name
+        #[cfg(feature = "anchor-compat")]
+        let borsh_input = crate::anchor::sighash_to_borsh(input);
+        #[cfg(feature = "anchor-compat")]
+        let input = borsh_input.as_deref().unwrap_or(input);
+        let payload = try_from_slice_unchecked::<VersionProgramInstruction>(input)
+            .or_else(|e| match input.split_first() {
+                Some((0, args)) => try_from_slice_unchecked::<InitializeAccountArgsV4>(args)
+                    .map(|args| VersionProgramInstruction::InitializeAccount(args.into())),
+                _ => Err(e),
+            })
+            .map_err(|_| {
+                log_error!("Fail: Instruction data could not be decoded");
+                DataVersionError::DeserializationFailure
+            })?;
         match payload {
-            VersionProgramInstruction::InitializeAccount => Ok(payload),
+            VersionProgramInstruction::InitializeAccount(_) => Ok(payload),
             VersionProgramInstruction::SetU64Value(_) => Ok(payload),
             VersionProgramInstruction::SetString(_) => Ok(payload), // Added with data version change
+            VersionProgramInstruction::SetAuthority(_) => Ok(payload),
+            VersionProgramInstruction::MigrateAccount => Ok(payload),
+            VersionProgramInstruction::GetState => Ok(payload),
+            VersionProgramInstruction::GetVersionInfo => Ok(payload),
+            VersionProgramInstruction::CompareAndSetU64 { .. } => Ok(payload),
+            VersionProgramInstruction::CompareAndSetString { .. } => Ok(payload),
+            VersionProgramInstruction::Batch(ref updates) => {
+                if updates.len() > MAX_BATCH_LEN {
+                    log_error!("Batch exceeds {} updates", MAX_BATCH_LEN);
+                    return Err(DataVersionError::InvalidInstruction.into());
+                }
+                Ok(payload)
+            }
+            VersionProgramInstruction::FreezeAccount => Ok(payload),
+            VersionProgramInstruction::ThawAccount => Ok(payload),
+            VersionProgramInstruction::CloseAccount => Ok(payload),
+            VersionProgramInstruction::InitializeConfig => Ok(payload),
+            VersionProgramInstruction::UpdateConfig(_) => Ok(payload),
+            VersionProgramInstruction::ForceMigrate => Ok(payload),
+            VersionProgramInstruction::RewriteHeader { .. } => Ok(payload),
+            VersionProgramInstruction::SetConfig(_) => Ok(payload),
+            VersionProgramInstruction::AddU64 { .. } => Ok(payload),
+            VersionProgramInstruction::SubU64 { .. } => Ok(payload),
+            VersionProgramInstruction::IncrementU64(_) => Ok(payload),
+            VersionProgramInstruction::SetField { .. } => Ok(payload),
+            VersionProgramInstruction::RemoveField(_) => Ok(payload),
+            VersionProgramInstruction::SetU64ValueSigned { .. } => Ok(payload),
+            VersionProgramInstruction::SetStringSigned { .. } => Ok(payload),
+            VersionProgramInstruction::ScheduleMigration { .. } => Ok(payload),
             _ => Err(DataVersionError::InvalidInstruction.into()),
         }
     }
+
+    /// True when the instruction reads, mutates or migrates a program
+    /// account, which takes the config account following the program account
+    pub fn reads_config(&self) -> bool {
+        matches!(
+            self,
+            VersionProgramInstruction::SetU64Value(_)
+                | VersionProgramInstruction::SetString(_)
+                | VersionProgramInstruction::SetAuthority(_)
+                | VersionProgramInstruction::MigrateAccount
+                | VersionProgramInstruction::GetState
+                | VersionProgramInstruction::CompareAndSetU64 { .. }
+                | VersionProgramInstruction::CompareAndSetString { .. }
+                | VersionProgramInstruction::Batch(_)
+                | VersionProgramInstruction::FreezeAccount
+                | VersionProgramInstruction::ThawAccount
+                | VersionProgramInstruction::CloseAccount
+                | VersionProgramInstruction::ForceMigrate
+                | VersionProgramInstruction::AddU64 { .. }
+                | VersionProgramInstruction::SubU64 { .. }
+                | VersionProgramInstruction::IncrementU64(_)
+                | VersionProgramInstruction::SetField { .. }
+                | VersionProgramInstruction::RemoveField(_)
+                | VersionProgramInstruction::SetU64ValueSigned { .. }
+                | VersionProgramInstruction::SetStringSigned { .. }
+        )
+    }
+
+    /// Accounts the instruction expects, which the processor checks before
+    /// dispatching it
+    pub fn account_specs(&self) -> AccountSpecs {
+        let program_account = AccountSpec::new("program account")
+            .writable()
+            .owner(AccountOwner::Program);
+        let config = AccountSpec::new("config").address(AccountAddress::Config);
+        let authority = AccountSpec::new("authority signer").signer();
+        let upgrade_authority = AccountSpec::new("upgrade authority").signer();
+        let program_data = AccountSpec::new("program data").address(AccountAddress::ProgramData);
+        let payer = AccountSpec::new("payer").writable().signer();
+        let system_program =
+            AccountSpec::new("system program").address(AccountAddress::Key(system_program::id()));
+        match self {
+            VersionProgramInstruction::InitializeAccount(InitializeAccountArgs {
+                label: None,
+                ..
+            }) => AccountSpecs::new(vec![program_account]),
+            // The handler checks the address derived from the owner and label
+            VersionProgramInstruction::InitializeAccount(_) => AccountSpecs::new(vec![
+                AccountSpec::new("program derived account").writable(),
+                AccountSpec::new("owner").signer(),
+                payer,
+                system_program,
+            ]),
+            VersionProgramInstruction::SetU64Value(_)
+            | VersionProgramInstruction::SetString(_)
+            | VersionProgramInstruction::SetAuthority(_)
+            | VersionProgramInstruction::MigrateAccount
+            | VersionProgramInstruction::CompareAndSetU64 { .. }
+            | VersionProgramInstruction::CompareAndSetString { .. }
+            | VersionProgramInstruction::Batch(_)
+            | VersionProgramInstruction::FreezeAccount
+            | VersionProgramInstruction::ThawAccount
+            | VersionProgramInstruction::AddU64 { .. }
+            | VersionProgramInstruction::SubU64 { .. }
+            | VersionProgramInstruction::IncrementU64(_)
+            | VersionProgramInstruction::SetField { .. }
+            | VersionProgramInstruction::RemoveField(_) => {
+                AccountSpecs::new(vec![program_account, config]).remaining(authority)
+            }
+            VersionProgramInstruction::CloseAccount => {
+                AccountSpecs::new(vec![program_account, config])
+                    .remaining(authority)
+                    .last(AccountSpec::new("destination").writable())
+            }
+            VersionProgramInstruction::GetState => AccountSpecs::new(vec![
+                AccountSpec::new("program account").owner(AccountOwner::Program),
+                config,
+            ]),
+            VersionProgramInstruction::GetVersionInfo
+            | VersionProgramInstruction::FailInstruction => AccountSpecs::new(vec![]),
+            // The config account is yet to be created
+            VersionProgramInstruction::InitializeConfig => AccountSpecs::new(vec![
+                config.writable(),
+                upgrade_authority,
+                payer,
+                system_program,
+                program_data,
+            ]),
+            VersionProgramInstruction::UpdateConfig(_) => AccountSpecs::new(vec![
+                config.writable().owner(AccountOwner::Program),
+                AccountSpec::new("admin").signer(),
+            ]),
+            VersionProgramInstruction::ForceMigrate => AccountSpecs::new(vec![
+                program_account,
+                config,
+                upgrade_authority,
+                program_data,
+            ]),
+            VersionProgramInstruction::RewriteHeader { .. } => {
+                AccountSpecs::new(vec![program_account, upgrade_authority, program_data])
+            }
+            VersionProgramInstruction::SetConfig(_)
+            | VersionProgramInstruction::ScheduleMigration { .. } => AccountSpecs::new(vec![
+                config.writable().owner(AccountOwner::Program),
+                upgrade_authority,
+                program_data,
+            ]),
+            VersionProgramInstruction::SetU64ValueSigned { .. }
+            | VersionProgramInstruction::SetStringSigned { .. } => AccountSpecs::new(vec![
+                program_account,
+                config,
+                AccountSpec::new("instructions sysvar")
+                    .address(AccountAddress::Key(sysvar::instructions::id())),
+            ]),
+        }
+    }
+}
+
+/// Required owner of an instruction account
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccountOwner {
+    Any,
+    /// Owned by this program
+    Program,
+}
+
+/// Required address of an instruction account
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccountAddress {
+    Any,
+    /// The config account derived from `CONFIG_SEED`
+    Config,
+    /// The program data derived from the program id by the upgradeable loader
+    ProgramData,
+    Key(Pubkey),
+}
+
+/// Expected properties of one instruction account
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AccountSpec {
+    /// Name the validator reports the account by
+    pub name: &'static str,
+    pub writable: bool,
+    pub signer: bool,
+    pub owner: AccountOwner,
+    pub address: AccountAddress,
+}
+
+impl AccountSpec {
+    /// Read only account that need not sign, with any owner and address
+    pub const fn new(name: &'static str) -> Self {
+        AccountSpec {
+            name,
+            writable: false,
+            signer: false,
+            owner: AccountOwner::Any,
+            address: AccountAddress::Any,
+        }
+    }
+    pub const fn writable(self) -> Self {
+        AccountSpec {
+            writable: true,
+            ..self
+        }
+    }
+    pub const fn signer(self) -> Self {
+        AccountSpec {
+            signer: true,
+            ..self
+        }
+    }
+    pub const fn owner(self, owner: AccountOwner) -> Self {
+        AccountSpec { owner, ..self }
+    }
+    pub const fn address(self, address: AccountAddress) -> Self {
+        AccountSpec { address, ..self }
+    }
+}
+
+/// Accounts of an instruction: the accounts in order, then any number
+/// matching `remaining` and finally, when given, the `last` account.
+/// Further accounts are refused
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccountSpecs {
+    pub accounts: Vec<AccountSpec>,
+    pub remaining: Option<AccountSpec>,
+    pub last: Option<AccountSpec>,
+}
+
+impl AccountSpecs {
+    pub fn new(accounts: Vec<AccountSpec>) -> Self {
+        AccountSpecs {
+            accounts,
+            remaining: None,
+            last: None,
+        }
+    }
+    pub fn remaining(self, remaining: AccountSpec) -> Self {
+        AccountSpecs {
+            remaining: Some(remaining),
+            ..self
+        }
+    }
+    pub fn last(self, last: AccountSpec) -> Self {
+        AccountSpecs {
+            last: Some(last),
+            ..self
+        }
+    }
+
+    /// Spec of the account at `index` among `len` accounts, None when the
+    /// account is not expected
+    pub fn get(&self, index: usize, len: usize) -> Option<&AccountSpec> {
+        let last_index = self.last.map(|_| len.saturating_sub(1));
+        if let Some(spec) = self.accounts.get(index) {
+            Some(spec)
+        } else if Some(index) == last_index {
+            self.last.as_ref()
+        } else {
+            self.remaining.as_ref()
+        }
+    }
+
+    /// Fewest accounts the instruction takes
+    pub fn min_len(&self) -> usize {
+        self.accounts.len() + usize::from(self.last.is_some())
+    }
+
+    /// Whether any account is expected at the address
+    pub fn expects_address(&self, address: AccountAddress) -> bool {
+        self.accounts
+            .iter()
+            .chain(&self.remaining)
+            .chain(&self.last)
+            .any(|spec| spec.address == address)
+    }
+}
+
+/// Accounts for instructions that update a program account:
+///   0. `[writable]` The program account
+///   1. `[]` The config account, see `find_config_address`
+///   2. ..2+N `[signer]` The N authority signers, none if the account
+///      has no authority
+fn update_account_metas(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Vec<AccountMeta> {
+    let (config_pubkey, _) = find_config_address(program_id);
+    let mut accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(config_pubkey, false),
+    ];
+    accounts.extend(
+        signer_pubkeys
+            .iter()
+            .map(|signer_pubkey| AccountMeta::new_readonly(**signer_pubkey, true)),
+    );
+    accounts
+}
+
+/// Creates an `InitializeAccount` instruction for an existing account,
+/// which anyone may update unless given an authority
+///
+/// Accounts expected:
+///   0. `[writable]` The program owned, uninitialized account
+pub fn initialize_account(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    somevalue: u64,
+    somestring: String,
+    authority: Option<AccountAuthority>,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::InitializeAccount(InitializeAccountArgs {
+            somevalue,
+            somestring,
+            label: None,
+            authority,
+        }),
+        vec![AccountMeta::new(*account_pubkey, false)],
+    )
+}
+
+/// Creates an `InitializeAccount` instruction that creates the program
+/// derived account for the owner and label, funded by the payer. The owner
+/// is the account authority unless given another
+///
+/// Accounts expected:
+///   0. `[writable]` The program derived account, see `find_account_address`
+///   1. `[signer]` The owner wallet
+///   2. `[writable, signer]` The payer of the account rent
+///   3. `[]` The system program
+pub fn initialize_derived_account(
+    program_id: &Pubkey,
+    owner_pubkey: &Pubkey,
+    payer_pubkey: &Pubkey,
+    label: String,
+    somevalue: u64,
+    somestring: String,
+    authority: Option<AccountAuthority>,
+) -> Instruction {
+    let (account_pubkey, _) = find_account_address(program_id, owner_pubkey, &label);
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::InitializeAccount(InitializeAccountArgs {
+            somevalue,
+            somestring,
+            label: Some(label),
+            authority,
+        }),
+        vec![
+            AccountMeta::new(account_pubkey, false),
+            AccountMeta::new_readonly(*owner_pubkey, true),
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates a `SetU64Value` instruction
+pub fn set_u64_value(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    value: u64,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::SetU64Value(value),
+        update_account_metas(program_id, account_pubkey, signer_pubkeys),
+    )
+}
+
+/// Creates a `SetString` instruction
+pub fn set_string_value(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    value: String,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::SetString(value),
+        update_account_metas(program_id, account_pubkey, signer_pubkeys),
+    )
+}
+
+/// Creates a `SetAuthority` instruction, signed by the current authority
+pub fn set_authority(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    authority: AccountAuthority,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::SetAuthority(authority),
+        update_account_metas(program_id, account_pubkey, signer_pubkeys),
+    )
+}
+
+/// Creates a `MigrateAccount` instruction
+pub fn migrate_account(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::MigrateAccount,
+        update_account_metas(program_id, account_pubkey, signer_pubkeys),
+    )
+}
+
+/// Creates a `GetState` instruction
+///
+/// Accounts expected:
+///   0. `[]` The program account
+///   1. `[]` The config account, see `find_config_address`
+pub fn get_state(program_id: &Pubkey, account_pubkey: &Pubkey) -> Instruction {
+    let (config_pubkey, _) = find_config_address(program_id);
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::GetState,
+        vec![
+            AccountMeta::new_readonly(*account_pubkey, false),
+            AccountMeta::new_readonly(config_pubkey, false),
+        ],
+    )
+}
+
+/// Creates a `GetVersionInfo` instruction, which takes no accounts
+pub fn get_version_info(program_id: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::GetVersionInfo,
+        vec![],
+    )
+}
+
+/// Creates a `CompareAndSetU64` instruction
+pub fn compare_and_set_u64(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    expected_seq: u64,
+    value: u64,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::CompareAndSetU64 {
+            expected_seq,
+            value,
+        },
+        update_account_metas(program_id, account_pubkey, signer_pubkeys),
+    )
+}
+
+/// Creates a `CompareAndSetString` instruction
+pub fn compare_and_set_string(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    expected_seq: u64,
+    value: String,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::CompareAndSetString {
+            expected_seq,
+            value,
+        },
+        update_account_metas(program_id, account_pubkey, signer_pubkeys),
+    )
+}
+
+/// Creates a `Batch` instruction
+pub fn batch(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    updates: Vec<AccountUpdate>,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::Batch(updates),
+        update_account_metas(program_id, account_pubkey, signer_pubkeys),
+    )
+}
+
+/// Creates a `FreezeAccount` instruction, signed by the authority
+pub fn freeze_account(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::FreezeAccount,
+        update_account_metas(program_id, account_pubkey, signer_pubkeys),
+    )
+}
+
+/// Creates a `ThawAccount` instruction, signed by the authority
+pub fn thaw_account(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::ThawAccount,
+        update_account_metas(program_id, account_pubkey, signer_pubkeys),
+    )
+}
+
+/// Creates a `CloseAccount` instruction, signed by the authority
+///
+/// Accounts expected:
+///   0. `[writable]` The program account
+///   1. `[]` The config account
+///   2. ..2+N `[signer]` The N authority signers, followed by the
+///      `[writable]` account receiving the lamports
+pub fn close_account(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    destination_pubkey: &Pubkey,
+) -> Instruction {
+    let mut accounts = update_account_metas(program_id, account_pubkey, signer_pubkeys);
+    accounts.push(AccountMeta::new(*destination_pubkey, false));
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::CloseAccount,
+        accounts,
+    )
+}
+
+/// Creates an `InitializeConfig` instruction
+///
+/// Accounts expected:
+///   0. `[writable]` The config account, see `find_config_address`
+///   1. `[signer]` The program upgrade authority, recorded as admin
+///   2. `[writable, signer]` The payer of the account rent
+///   3. `[]` The system program
+///   4. `[]` The program data, see `find_program_data_address`
+pub fn initialize_config(
+    program_id: &Pubkey,
+    admin_pubkey: &Pubkey,
+    payer_pubkey: &Pubkey,
+) -> Instruction {
+    let (config_pubkey, _) = find_config_address(program_id);
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::InitializeConfig,
+        vec![
+            AccountMeta::new(config_pubkey, false),
+            AccountMeta::new_readonly(*admin_pubkey, true),
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(find_program_data_address(program_id), false),
+        ],
+    )
+}
+
+/// Creates an `UpdateConfig` instruction
+///
+/// Accounts expected:
+///   0. `[writable]` The config account
+///   1. `[signer]` The admin
+pub fn update_config(
+    program_id: &Pubkey,
+    admin_pubkey: &Pubkey,
+    update: ConfigUpdate,
+) -> Instruction {
+    let (config_pubkey, _) = find_config_address(program_id);
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::UpdateConfig(update),
+        vec![
+            AccountMeta::new(config_pubkey, false),
+            AccountMeta::new_readonly(*admin_pubkey, true),
+        ],
+    )
+}
+
+/// Creates a `ForceMigrate` instruction
+///
+/// Accounts expected:
+///   0. `[writable]` The program account
+///   1. `[]` The config account
+///   2. `[signer]` The program upgrade authority
+///   3. `[]` The program data, see `find_program_data_address`
+pub fn force_migrate(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+) -> Instruction {
+    let (config_pubkey, _) = find_config_address(program_id);
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::ForceMigrate,
+        vec![
+            AccountMeta::new(*account_pubkey, false),
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+            AccountMeta::new_readonly(find_program_data_address(program_id), false),
+        ],
+    )
+}
+
+/// Creates a `RewriteHeader` instruction
+///
+/// Accounts expected:
+///   0. `[writable]` The program account
+///   1. `[signer]` The program upgrade authority
+///   2. `[]` The program data, see `find_program_data_address`
+pub fn rewrite_header(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    lifecycle: AccountLifecycle,
+    data_version: u8,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::RewriteHeader {
+            lifecycle,
+            data_version,
+        },
+        vec![
+            AccountMeta::new(*account_pubkey, false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+            AccountMeta::new_readonly(find_program_data_address(program_id), false),
+        ],
+    )
+}
+
+/// Creates a `SetConfig` instruction
+///
+/// Accounts expected:
+///   0. `[writable]` The config account
+///   1. `[signer]` The program upgrade authority
+///   2. `[]` The program data, see `find_program_data_address`
+pub fn set_config(
+    program_id: &Pubkey,
+    authority_pubkey: &Pubkey,
+    update: ConfigUpdate,
+) -> Instruction {
+    let (config_pubkey, _) = find_config_address(program_id);
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::SetConfig(update),
+        vec![
+            AccountMeta::new(config_pubkey, false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+            AccountMeta::new_readonly(find_program_data_address(program_id), false),
+        ],
+    )
+}
+
+/// Creates an `AddU64` instruction
+pub fn add_u64(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u64,
+    bounds: ValueBounds,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::AddU64 { amount, bounds },
+        update_account_metas(program_id, account_pubkey, signer_pubkeys),
+    )
+}
+
+/// Creates a `SubU64` instruction
+pub fn sub_u64(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u64,
+    bounds: ValueBounds,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::SubU64 { amount, bounds },
+        update_account_metas(program_id, account_pubkey, signer_pubkeys),
+    )
+}
+
+/// Creates an `IncrementU64` instruction
+pub fn increment_u64(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    bounds: ValueBounds,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::IncrementU64(bounds),
+        update_account_metas(program_id, account_pubkey, signer_pubkeys),
+    )
+}
+
+/// Creates a `SetField` instruction
+pub fn set_field(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    key: String,
+    value: FieldValue,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::SetField { key, value },
+        update_account_metas(program_id, account_pubkey, signer_pubkeys),
+    )
+}
+
+/// Creates a `RemoveField` instruction
+pub fn remove_field(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    key: String,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::RemoveField(key),
+        update_account_metas(program_id, account_pubkey, signer_pubkeys),
+    )
+}
+
+/// Accounts for instructions that apply an update signed off-chain:
+///   0. `[writable]` The program account
+///   1. `[]` The config account
+///   2. `[]` The instructions sysvar, through which the Ed25519 program
+///      instructions preceding this one are read
+fn signed_update_metas(program_id: &Pubkey, account_pubkey: &Pubkey) -> Vec<AccountMeta> {
+    let (config_pubkey, _) = find_config_address(program_id);
+    vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ]
+}
+
+/// Creates a `SetU64ValueSigned` instruction, which must follow an Ed25519
+/// program instruction with the authority signature of the `SignedUpdate`
+pub fn set_u64_value_signed(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    value: u64,
+    sequence: u64,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::SetU64ValueSigned { value, sequence },
+        signed_update_metas(program_id, account_pubkey),
+    )
+}
+
+/// Creates a `SetStringSigned` instruction, which must follow an Ed25519
+/// program instruction with the authority signature of the `SignedUpdate`
+pub fn set_string_signed(
+    program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    value: String,
+    sequence: u64,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::SetStringSigned { value, sequence },
+        signed_update_metas(program_id, account_pubkey),
+    )
+}
+
+/// Creates a `ScheduleMigration` instruction
+///
+/// Accounts expected:
+///   0. `[writable]` The config account
+///   1. `[signer]` The program upgrade authority
+///   2. `[]` The program data, see `find_program_data_address`
+pub fn schedule_migration(
+    program_id: &Pubkey,
+    authority_pubkey: &Pubkey,
+    target_version: u8,
+    activation_slot: u64,
+) -> Instruction {
+    let (config_pubkey, _) = find_config_address(program_id);
+    Instruction::new_with_borsh(
+        *program_id,
+        &VersionProgramInstruction::ScheduleMigration {
+            target_version,
+            activation_slot,
+        },
+        vec![
+            AccountMeta::new(config_pubkey, false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+            AccountMeta::new_readonly(find_program_data_address(program_id), false),
+        ],
+    )
 }
@@ -4,9 +4,17 @@
 pub use solana_program;
 
 pub mod account_state;
+pub mod admin;
+#[cfg(feature = "anchor-compat")]
+pub mod anchor;
+pub mod config;
+pub mod cpi;
 pub mod entry_point;
 pub mod error;
+pub mod events;
 pub mod instruction;
+mod logging;
 pub mod processor;
+pub mod signed_update;
 
 solana_program::declare_id!("PWDnx8LkjJUn9bAVzG6Fp6BuvB41x7DkBZdo9YLMGcc");
@@ -0,0 +1,42 @@
+//! Logging facade whose level is selected with cargo features:
+//! `log-error`, `log-info` (the default) or `log-debug`, each including
+//! the levels above it. `log-off` silences all program logs, events
+//! included
+
+/// Failures, logged with the `log-error` feature
+pub const ERROR_ENABLED: bool = cfg!(all(feature = "log-error", not(feature = "log-off")));
+/// State changes, logged with the `log-info` feature
+pub const INFO_ENABLED: bool = cfg!(all(feature = "log-info", not(feature = "log-off")));
+/// Processing details and payloads, logged with the `log-debug` feature
+pub const DEBUG_ENABLED: bool = cfg!(all(feature = "log-debug", not(feature = "log-off")));
+/// Events, logged unless `log-off` is selected
+pub const EVENTS_ENABLED: bool = cfg!(not(feature = "log-off"));
+
+/// Logs a failure
+macro_rules! log_error {
+    ($($arg:tt)+) => {
+        if $crate::logging::ERROR_ENABLED {
+            solana_program::msg!($($arg)+);
+        }
+    };
+}
+
+/// Logs a state change
+macro_rules! log_info {
+    ($($arg:tt)+) => {
+        if $crate::logging::INFO_ENABLED {
+            solana_program::msg!($($arg)+);
+        }
+    };
+}
+
+/// Logs processing details
+macro_rules! log_debug {
+    ($($arg:tt)+) => {
+        if $crate::logging::DEBUG_ENABLED {
+            solana_program::msg!($($arg)+);
+        }
+    };
+}
+
+pub(crate) use {log_debug, log_error, log_info};
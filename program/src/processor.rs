@@ -1,42 +1,432 @@
 //! Resolve instruction and execute
 
 use crate::{
-    account_state::ProgramAccountState, error::DataVersionError,
-    instruction::VersionProgramInstruction,
+    account_state::{
+        find_account_address, AccountAuthority, AccountLifecycle, ProgramAccountState, VersionInfo,
+        ACCOUNT_SEED_PREFIX, ACCOUNT_STATE_SPACE, PROGRAM_VERSION,
+    },
+    admin,
+    config::{
+        find_config_address, ConfigUpdate, ProgramConfig, ScheduledMigration, CONFIG_SEED,
+        CONFIG_SPACE,
+    },
+    error::DataVersionError,
+    events::ProgramEvent,
+    instruction::{
+        AccountAddress, AccountOwner, AccountSpec, AccountSpecs, AccountUpdate,
+        InitializeAccountArgs, ValueBounds, VersionProgramInstruction,
+    },
+    logging::{log_debug, log_error, log_info},
+    signed_update::{ed25519_signers, SignedField, SignedUpdate},
 };
+use borsh::BorshSerialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
-    msg,
+    program::{invoke_signed, set_return_data},
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack},
-    pubkey::Pubkey,
+    pubkey::{Pubkey, MAX_SEED_LEN},
+    rent::Rent,
+    system_instruction, system_program,
+    sysvar::Sysvar,
 };
 
-/// Checks each tracking account to confirm it is owned by our program
-/// This function assumes that the program account is always the last
-/// in the array
-fn check_account_ownership(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    // Accounts must be owned by the program.
-    for account in accounts.iter().take(accounts.len() - 1) {
-        if account.owner != program_id {
-            msg!(
-                "Fail: The tracking account owner is {} and it should be {}.",
-                account.owner,
-                program_id
-            );
-            return Err(ProgramError::IncorrectProgramId);
+/// Checks the account against its spec, naming it by index on failure
+fn check_account(
+    program_id: &Pubkey,
+    config_address: Option<&(Pubkey, u8)>,
+    index: usize,
+    account: &AccountInfo,
+    spec: &AccountSpec,
+) -> ProgramResult {
+    if spec.writable && !account.is_writable {
+        log_error!("Fail: Account {} ({}) is not writable", index, spec.name);
+        return Err(DataVersionError::AccountNotWritable.into());
+    }
+    if spec.signer && !account.is_signer {
+        log_error!("Fail: Account {} ({}) has not signed", index, spec.name);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if spec.owner == AccountOwner::Program && account.owner != program_id {
+        log_error!(
+            "Fail: Account {} ({}) owner is {} and it should be {}",
+            index,
+            spec.name,
+            account.owner,
+            program_id
+        );
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let address = match spec.address {
+        AccountAddress::Any => return Ok(()),
+        AccountAddress::Config => config_address
+            .map(|(address, _)| *address)
+            .unwrap_or_else(|| find_config_address(program_id).0),
+        AccountAddress::ProgramData => admin::find_program_data_address(program_id),
+        AccountAddress::Key(key) => key,
+    };
+    if *account.key != address {
+        log_error!(
+            "Fail: Account {} ({}) is {} and it should be {}",
+            index,
+            spec.name,
+            account.key,
+            address
+        );
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
+
+/// Checks the accounts against the specs the instruction declares,
+/// refusing missing and unexpected accounts. The config address is the
+/// one `process` derived, if any
+fn validate_accounts(
+    program_id: &Pubkey,
+    config_address: Option<&(Pubkey, u8)>,
+    accounts: &[AccountInfo],
+    specs: &AccountSpecs,
+) -> ProgramResult {
+    if accounts.len() < specs.min_len() {
+        log_error!(
+            "Fail: Expected at least {} accounts but got {}",
+            specs.min_len(),
+            accounts.len()
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    for (index, account) in accounts.iter().enumerate() {
+        match specs.get(index, accounts.len()) {
+            Some(spec) => check_account(program_id, config_address, index, account, spec)?,
+            None => {
+                log_error!("Fail: Unexpected account {} {}", index, account.key);
+                return Err(DataVersionError::UnexpectedAccount.into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Confirms the account authority has signed, counting the distinct
+/// multisig signers found in the accounts following the program account
+fn check_authority(account_state: &ProgramAccountState, signers: &[AccountInfo]) -> ProgramResult {
+    check_authority_signed(account_state, |key| {
+        signers
+            .iter()
+            .any(|account| account.is_signer && account.key == key)
+    })
+}
+
+/// Confirms the account authority has signed, where `has_signed` tells
+/// whether a key has signed
+fn check_authority_signed(
+    account_state: &ProgramAccountState,
+    has_signed: impl Fn(&Pubkey) -> bool,
+) -> ProgramResult {
+    match account_state.authority() {
+        AccountAuthority::None => Ok(()),
+        AccountAuthority::Single(key) => {
+            if has_signed(key) {
+                Ok(())
+            } else {
+                log_error!("Fail: Authority {} has not signed", key);
+                Err(DataVersionError::Unauthorized.into())
+            }
+        }
+        AccountAuthority::Multisig { threshold, signers } => {
+            let signed = signers.iter().filter(|key| has_signed(key)).count();
+            if signed >= *threshold as usize {
+                Ok(())
+            } else {
+                log_error!("Fail: {} of {} required signers signed", signed, threshold);
+                Err(DataVersionError::Unauthorized.into())
+            }
         }
     }
+}
+
+/// Fails unless the account is in one of the permitted lifecycle states
+fn check_lifecycle(
+    account_state: &ProgramAccountState,
+    permitted: &[AccountLifecycle],
+) -> ProgramResult {
+    if permitted.contains(&account_state.lifecycle()) {
+        Ok(())
+    } else {
+        log_error!(
+            "Fail: Operation not permitted on {:?} account",
+            account_state.lifecycle()
+        );
+        match account_state.lifecycle() {
+            AccountLifecycle::Frozen => Err(DataVersionError::AccountFrozen.into()),
+            _ => Err(DataVersionError::InvalidLifecycleState.into()),
+        }
+    }
+}
+
+/// Fails if the account has no authority, as anyone could otherwise
+/// freeze or close it
+fn require_authority(account_state: &ProgramAccountState) -> ProgramResult {
+    if *account_state.authority() == AccountAuthority::None {
+        log_error!("Fail: Account has no authority");
+        Err(DataVersionError::AuthorityRequired.into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Creates a program derived account of `space` bytes at the address of
+/// `seeds`, funded by the payer to be rent exempt
+fn create_program_account<'a>(
+    program_id: &Pubkey,
+    program_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let from_len = program_account.data_len() as u64;
+    let lamports = Rent::get()?.minimum_balance(space);
+    let infos = [
+        payer.clone(),
+        program_account.clone(),
+        system_program.clone(),
+    ];
+    if program_account.lamports() == 0 {
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                program_account.key,
+                lamports,
+                space as u64,
+                program_id,
+            ),
+            &infos,
+            &[seeds],
+        )?;
+    } else {
+        // Anyone may fund the address ahead of its creation, which
+        // create_account refuses, so top it up, allocate and assign instead.
+        // Assigning succeeds on an account the program owns already
+        if *program_account.owner != system_program::id() {
+            log_error!("Fail: Account {} already exists", program_account.key);
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        let shortfall = lamports.saturating_sub(program_account.lamports());
+        if shortfall > 0 {
+            invoke_signed(
+                &system_instruction::transfer(payer.key, program_account.key, shortfall),
+                &infos,
+                &[],
+            )?;
+        }
+        if program_account.data_len() != space {
+            invoke_signed(
+                &system_instruction::allocate(program_account.key, space as u64),
+                &infos,
+                &[seeds],
+            )?;
+        }
+        invoke_signed(
+            &system_instruction::assign(program_account.key, program_id),
+            &infos,
+            &[seeds],
+        )?;
+    }
+    ProgramEvent::AccountResized {
+        account: *program_account.key,
+        from_len,
+        to_len: space as u64,
+    }
+    .emit();
     Ok(())
 }
 
-/// Initialize the programs account, which is the first in accounts
-fn initialize_account(accounts: &[AccountInfo]) -> ProgramResult {
-    msg!("Initialize account");
+/// Creates the program derived account for the owner and label,
+/// funded by the payer, returning its bump seed and the owner
+fn create_derived_account<'a>(
+    program_id: &Pubkey,
+    program_account: &AccountInfo<'a>,
+    label: &str,
+    account_info_iter: &mut std::slice::Iter<AccountInfo<'a>>,
+) -> Result<(u8, Pubkey), ProgramError> {
+    let owner = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    if !owner.is_signer {
+        log_error!("Fail: Owner {} has not signed", owner.key);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if label.len() > MAX_SEED_LEN {
+        log_error!("Fail: Label exceeds {} bytes", MAX_SEED_LEN);
+        return Err(ProgramError::InvalidArgument);
+    }
+    let (address, bump_seed) = find_account_address(program_id, owner.key, label);
+    if address != *program_account.key {
+        log_error!(
+            "Fail: Account {} is not the derived address {}",
+            program_account.key,
+            address
+        );
+        return Err(ProgramError::InvalidSeeds);
+    }
+    log_info!("Create derived account {}", address);
+    create_program_account(
+        program_id,
+        program_account,
+        payer,
+        system_program,
+        ACCOUNT_STATE_SPACE,
+        &[
+            ACCOUNT_SEED_PREFIX,
+            owner.key.as_ref(),
+            label.as_bytes(),
+            &[bump_seed],
+        ],
+    )?;
+    Ok((bump_seed, *owner.key))
+}
+
+/// Reads the config account, whose address the account specs checked,
+/// falling back to the default config until the admin has created it
+fn load_config(
+    program_id: &Pubkey,
+    config_account: &AccountInfo,
+) -> Result<ProgramConfig, ProgramError> {
+    if config_account.owner != program_id {
+        log_debug!("Config not created, using defaults");
+        return Ok(ProgramConfig::default());
+    }
+    if config_account.data_len() != ProgramConfig::LEN {
+        log_error!("Fail: Account {} is not a config", config_account.key);
+        return Err(DataVersionError::WrongAccountKind.into());
+    }
+    ProgramConfig::unpack(&config_account.data.borrow())
+}
+
+/// Reads the programs account under the config, backlevel fields taking
+/// the config defaults, refusing data older than the minimum readable
+/// version
+fn read_account(
+    account_data: &[u8],
+    config: &ProgramConfig,
+) -> Result<ProgramAccountState, ProgramError> {
+    let account_state =
+        ProgramAccountState::unpack_with_defaults(account_data, &config.field_defaults)?;
+    let stored_version = account_data[1];
+    if stored_version < config.min_readable_version {
+        log_error!(
+            "Fail: Data version {} is below minimum {}",
+            stored_version,
+            config.min_readable_version
+        );
+        return Err(DataVersionError::VersionBelowMinimum.into());
+    }
+    Ok(account_state)
+}
+
+/// Unpacks the programs account under the config, refusing data older
+/// than the minimum readable version and, while migrations are paused,
+/// backlevel data that writing would migrate. The account is written at
+/// the data version the config gives for the current slot
+fn unpack_account(
+    account_data: &[u8],
+    config: &ProgramConfig,
+) -> Result<ProgramAccountState, ProgramError> {
+    let mut account_state = read_account(account_data, config)?;
+    let stored_version = account_data[1];
+    // Until a scheduled migration activates the account keeps an older layout
+    account_state.set_version(config.write_version(stored_version, || Ok(Clock::get()?.slot))?)?;
+    if config.migrations_paused && stored_version != account_state.version() {
+        log_error!("Fail: Migrations are paused");
+        return Err(DataVersionError::MigrationsPaused.into());
+    }
+    Ok(account_state)
+}
+
+/// Packs the programs account, logging the migration when writing changed
+/// its data version
+fn pack_account(
+    program_account: &AccountInfo,
+    account_state: ProgramAccountState,
+    account_data: &mut [u8],
+) -> ProgramResult {
+    let from_version = account_data[1];
+    let to_version = account_state.version();
+    ProgramAccountState::pack(account_state, account_data)?;
+    if from_version != to_version {
+        ProgramEvent::AccountMigrated {
+            account: *program_account.key,
+            from_version,
+            to_version,
+        }
+        .emit();
+    }
+    Ok(())
+}
+
+/// Logs the write sequence of the programs account as stored by the pack.
+/// Every writable layout opens with the lifecycle, data version and write
+/// sequence
+fn emit_content_set(program_account: &AccountInfo, account_data: &[u8]) {
+    let mut write_sequence = [0u8; 8];
+    write_sequence.copy_from_slice(&account_data[2..10]);
+    ProgramEvent::ContentSet {
+        account: *program_account.key,
+        data_version: account_data[1],
+        write_sequence: u64::from_le_bytes(write_sequence),
+    }
+    .emit();
+}
+
+/// Initialize the programs account, which is the first in accounts,
+/// creating it first when a label is given
+fn initialize_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: InitializeAccountArgs,
+) -> ProgramResult {
+    log_info!("Initialize account");
+    if let Some(authority) = &args.authority {
+        if !authority.is_valid() {
+            log_error!("Fail: Invalid authority {:?}", authority);
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
     let account_info_iter = &mut accounts.iter();
     let program_account = next_account_info(account_info_iter)?;
+    // Derived accounts belong to the owner unless given another authority
+    let (bump_seed, authority) = match &args.label {
+        Some(label) => {
+            let (bump_seed, owner) =
+                create_derived_account(program_id, program_account, label, account_info_iter)?;
+            (
+                Some(bump_seed),
+                args.authority.unwrap_or(AccountAuthority::Single(owner)),
+            )
+        }
+        None => (None, args.authority.unwrap_or_default()),
+    };
     let mut account_data = program_account.data.borrow_mut();
+    // The version header is only written to accounts that hold the
+    // current version and stay funded
+    if account_data.len() < ProgramAccountState::LEN {
+        log_error!(
+            "Fail: Account data is {} bytes, the current version needs {}",
+            account_data.len(),
+            ProgramAccountState::LEN
+        );
+        return Err(DataVersionError::AccountTooSmall.into());
+    }
+    if !Rent::get()?.is_exempt(program_account.lamports(), account_data.len()) {
+        log_error!(
+            "Fail: Account balance {} is not rent exempt",
+            program_account.lamports()
+        );
+        return Err(DataVersionError::AccountNotRentExempt.into());
+    }
     // Just using unpack will check to see if initialized and will
     // fail if not
     let mut account_state = ProgramAccountState::unpack_unchecked(&account_data)?;
@@ -45,36 +435,361 @@ fn initialize_account(accounts: &[AccountInfo]) -> ProgramResult {
         return Err(DataVersionError::AlreadyInitializedState.into());
     } else {
         account_state.set_initialized();
-        account_state.content_mut().somevalue = 1;
+        if let Some(bump_seed) = bump_seed {
+            account_state.set_bump_seed(bump_seed);
+        }
+        account_state.set_authority(authority);
+        account_state.content_mut().somevalue = args.somevalue;
+        account_state.content_mut().somestring = args.somestring;
     }
-    msg!("Account Initialized");
+    log_info!("Account Initialized");
+    let data_version = account_state.version();
     // Serialize
-    ProgramAccountState::pack(account_state, &mut account_data)
+    ProgramAccountState::pack(account_state, &mut account_data)?;
+    ProgramEvent::AccountInitialized {
+        account: *program_account.key,
+        data_version,
+    }
+    .emit();
+    Ok(())
 }
 
 /// Sets the u64 in the content structure
-fn set_u64_value(accounts: &[AccountInfo], value: u64) -> ProgramResult {
-    msg!("Set new value {}", value);
+fn set_u64_value(account_state: &mut ProgramAccountState, value: u64) -> ProgramResult {
+    log_info!("Set new value {}", value);
+    account_state.content_mut().somevalue = value;
+    Ok(())
+}
+
+/// Sets the string in the content structure
+fn set_string_value(account_state: &mut ProgramAccountState, value: String) -> ProgramResult {
+    log_info!("Set new string of {} bytes", value.len());
+    log_debug!("Set new string {}", value);
+    account_state.content_mut().somestring = value;
+    Ok(())
+}
+
+/// Stores the result of arithmetic on the u64, when it did not overflow and
+/// is within the bounds, and returns the new value
+fn adjust_u64_value(
+    account_state: &mut ProgramAccountState,
+    result: Option<u64>,
+    bounds: ValueBounds,
+) -> ProgramResult {
+    let value = result.ok_or_else(|| {
+        log_error!(
+            "Fail: Arithmetic overflow on {}",
+            account_state.content().somevalue
+        );
+        DataVersionError::ArithmeticOverflow
+    })?;
+    if matches!(bounds.min, Some(min) if value < min)
+        || matches!(bounds.max, Some(max) if value > max)
+    {
+        log_error!("Fail: Value {} outside bounds {:?}", value, bounds);
+        return Err(DataVersionError::ValueOutOfBounds.into());
+    }
+    log_info!("Adjust value to {}", value);
+    account_state.content_mut().somevalue = value;
+    set_return_data(&value.try_to_vec()?);
+    Ok(())
+}
+
+/// Fails if the account was written since `expected_seq`
+fn check_sequence(account_state: &ProgramAccountState, expected_seq: u64) -> ProgramResult {
+    if account_state.write_sequence() == expected_seq {
+        Ok(())
+    } else {
+        log_error!(
+            "Fail: Expected sequence {} but account is at {}",
+            expected_seq,
+            account_state.write_sequence()
+        );
+        Err(DataVersionError::SequenceMismatch.into())
+    }
+}
+
+/// Replaces the authority, which the current authority has approved
+fn set_authority(
+    account_state: &mut ProgramAccountState,
+    authority: AccountAuthority,
+) -> ProgramResult {
+    log_info!("Set new authority");
+    if !authority.is_valid() {
+        log_error!("Fail: Invalid authority {:?}", authority);
+        return Err(ProgramError::InvalidArgument);
+    }
+    account_state.set_authority(authority);
+    Ok(())
+}
+
+/// Applies a single update to the unpacked account state. Compare and
+/// set updates compare against the sequence the account was unpacked at
+fn apply_update(account_state: &mut ProgramAccountState, update: AccountUpdate) -> ProgramResult {
+    match update {
+        AccountUpdate::SetU64Value(value) => set_u64_value(account_state, value),
+        AccountUpdate::SetString(value) => set_string_value(account_state, value),
+        AccountUpdate::SetAuthority(authority) => set_authority(account_state, authority),
+        AccountUpdate::CompareAndSetU64 {
+            expected_seq,
+            value,
+        } => {
+            check_sequence(account_state, expected_seq)?;
+            set_u64_value(account_state, value)
+        }
+        AccountUpdate::CompareAndSetString {
+            expected_seq,
+            value,
+        } => {
+            check_sequence(account_state, expected_seq)?;
+            set_string_value(account_state, value)
+        }
+        AccountUpdate::AddU64 { amount, bounds } => {
+            let result = account_state.content().somevalue.checked_add(amount);
+            adjust_u64_value(account_state, result, bounds)
+        }
+        AccountUpdate::SubU64 { amount, bounds } => {
+            let result = account_state.content().somevalue.checked_sub(amount);
+            adjust_u64_value(account_state, result, bounds)
+        }
+        AccountUpdate::SetField { key, value } => {
+            log_info!("Set field {}", key);
+            account_state.set_field(key, value)
+        }
+        AccountUpdate::RemoveField(key) => {
+            log_info!("Remove field {}", key);
+            match account_state.remove_field(&key) {
+                Some(_) => Ok(()),
+                None => Err(DataVersionError::FieldNotFound.into()),
+            }
+        }
+    }
+}
+
+/// Unpacks the programs account, which is the first in accounts, confirms
+/// the authority, applies the updates in order and packs once. Any failing
+/// update fails them all
+fn update_account(
+    accounts: &[AccountInfo],
+    config: &ProgramConfig,
+    updates: Vec<AccountUpdate>,
+) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let program_account = next_account_info(account_info_iter)?;
     let mut account_data = program_account.data.borrow_mut();
-    let mut account_state = ProgramAccountState::unpack(&account_data)?;
-    account_state.content_mut().somevalue = value;
+    let mut account_state = unpack_account(&account_data, config)?;
+    check_lifecycle(
+        &account_state,
+        &[AccountLifecycle::Active, AccountLifecycle::PendingMigration],
+    )?;
+    check_authority(&account_state, account_info_iter.as_slice())?;
+    for update in updates {
+        apply_update(&mut account_state, update)?;
+    }
     // Serialize
-    ProgramAccountState::pack(account_state, &mut account_data)
+    pack_account(program_account, account_state, &mut account_data)?;
+    emit_content_set(program_account, &account_data);
+    Ok(())
 }
 
-/// Sets the string in the content structure
-fn set_string_value(accounts: &[AccountInfo], value: String) -> ProgramResult {
-    msg!("Set new string {}", value);
+/// Rewrites a backlevel account at the current data version
+fn migrate_account(accounts: &[AccountInfo], config: &ProgramConfig) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let program_account = next_account_info(account_info_iter)?;
     let mut account_data = program_account.data.borrow_mut();
-    let mut account_state = ProgramAccountState::unpack(&account_data)?;
-    account_state.content_mut().somestring = value;
+    // Unpacking converts backlevel data to the current version
+    let account_state = unpack_account(&account_data, config)?;
+    let stored_version = account_data[1];
+    // Frozen accounts are only migrated when forced, see admin::force_migrate
+    check_lifecycle(
+        &account_state,
+        &[AccountLifecycle::Active, AccountLifecycle::PendingMigration],
+    )?;
+    check_authority(&account_state, account_info_iter.as_slice())?;
+    if stored_version == account_state.version() {
+        log_debug!("Account already at data version {}", stored_version);
+        return Ok(());
+    }
+    log_info!(
+        "Migrate account from data version {} to {}",
+        stored_version,
+        account_state.version()
+    );
     // Serialize
-    ProgramAccountState::pack(account_state, &mut account_data)
+    pack_account(program_account, account_state, &mut account_data)
 }
+
+/// Moves the account from one of the `from` lifecycle states to `to`,
+/// which the authority must approve
+fn change_lifecycle(
+    accounts: &[AccountInfo],
+    config: &ProgramConfig,
+    from: &[AccountLifecycle],
+    to: AccountLifecycle,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let program_account = next_account_info(account_info_iter)?;
+    let mut account_data = program_account.data.borrow_mut();
+    let mut account_state = unpack_account(&account_data, config)?;
+    check_lifecycle(&account_state, from)?;
+    require_authority(&account_state)?;
+    check_authority(&account_state, account_info_iter.as_slice())?;
+    log_info!("Account {:?} to {:?}", account_state.lifecycle(), to);
+    account_state.set_lifecycle(to);
+    // Serialize
+    pack_account(program_account, account_state, &mut account_data)
+}
+
+/// Marks the account closed and withdraws its lamports to the
+/// destination, which is the last of the accounts
+fn close_account(accounts: &[AccountInfo], config: &ProgramConfig) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let program_account = next_account_info(account_info_iter)?;
+    let (destination, signers) = account_info_iter
+        .as_slice()
+        .split_last()
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if destination.key == program_account.key {
+        log_error!("Fail: Destination is the account being closed");
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut account_data = program_account.data.borrow_mut();
+    let mut account_state = unpack_account(&account_data, config)?;
+    check_lifecycle(
+        &account_state,
+        &[AccountLifecycle::Active, AccountLifecycle::PendingMigration],
+    )?;
+    require_authority(&account_state)?;
+    check_authority(&account_state, signers)?;
+    log_info!("Close account to {}", destination.key);
+    account_state.set_lifecycle(AccountLifecycle::Closed);
+    pack_account(program_account, account_state, &mut account_data)?;
+    let lamports = program_account.lamports();
+    **destination.lamports.borrow_mut() = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::InvalidArgument)?;
+    **program_account.lamports.borrow_mut() = 0;
+    ProgramEvent::AccountClosed {
+        account: *program_account.key,
+        destination: *destination.key,
+        lamports,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Applies an update the authority signed off-chain, verified by Ed25519
+/// program instructions earlier in the transaction. The signed sequence
+/// must match the account, so the update can not be replayed
+fn set_signed_value(
+    accounts: &[AccountInfo],
+    config: &ProgramConfig,
+    field: SignedField,
+    sequence: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let program_account = next_account_info(account_info_iter)?;
+    let instructions_sysvar = next_account_info(account_info_iter)?;
+    let mut account_data = program_account.data.borrow_mut();
+    let mut account_state = unpack_account(&account_data, config)?;
+    check_lifecycle(
+        &account_state,
+        &[AccountLifecycle::Active, AccountLifecycle::PendingMigration],
+    )?;
+    require_authority(&account_state)?;
+    let message = SignedUpdate {
+        account: *program_account.key,
+        field: field.clone(),
+        sequence,
+    }
+    .message()?;
+    let signers = ed25519_signers(instructions_sysvar, &message)?;
+    check_authority_signed(&account_state, |key| signers.contains(key))?;
+    check_sequence(&account_state, sequence)?;
+    match field {
+        SignedField::SomeValue(value) => set_u64_value(&mut account_state, value)?,
+        SignedField::SomeString(value) => set_string_value(&mut account_state, value)?,
+    }
+    // Serialize
+    pack_account(program_account, account_state, &mut account_data)?;
+    emit_content_set(program_account, &account_data);
+    Ok(())
+}
+
+/// Returns the content, converted to the current data version as a
+/// write would store it, without modifying the account
+fn get_state(accounts: &[AccountInfo], config: &ProgramConfig) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let program_account = next_account_info(account_info_iter)?;
+    let account_state = read_account(&program_account.data.borrow(), config)?;
+    check_lifecycle(
+        &account_state,
+        &[
+            AccountLifecycle::Active,
+            AccountLifecycle::Frozen,
+            AccountLifecycle::PendingMigration,
+        ],
+    )?;
+    set_return_data(&account_state.content().try_to_vec()?);
+    Ok(())
+}
+
+/// Creates the config account at the address derived with `bump_seed`,
+/// recording the signer as admin. Only the upgrade authority may, or
+/// anyone could take the config over after a deploy
+fn initialize_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    bump_seed: u8,
+) -> ProgramResult {
+    log_info!("Initialize config");
+    let account_info_iter = &mut accounts.iter();
+    let config_account = next_account_info(account_info_iter)?;
+    let admin = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let program_data = next_account_info(account_info_iter)?;
+    admin::check_upgrade_authority(program_id, admin, program_data)?;
+    create_program_account(
+        program_id,
+        config_account,
+        payer,
+        system_program,
+        CONFIG_SPACE,
+        &[CONFIG_SEED, &[bump_seed]],
+    )?;
+    let config = ProgramConfig {
+        is_initialized: true,
+        bump_seed,
+        admin: *admin.key,
+        ..ProgramConfig::default()
+    };
+    ProgramConfig::pack(config, &mut config_account.data.borrow_mut())
+}
+
+/// Applies the changes to the config, which the admin must sign
+fn update_config(accounts: &[AccountInfo], update: ConfigUpdate) -> ProgramResult {
+    log_info!("Update config");
+    let account_info_iter = &mut accounts.iter();
+    let config_account = next_account_info(account_info_iter)?;
+    let admin = next_account_info(account_info_iter)?;
+    let mut config_data = config_account.data.borrow_mut();
+    let mut config = ProgramConfig::unpack(&config_data)?;
+    if !admin.is_signer || *admin.key != config.admin {
+        log_error!("Fail: Admin {} has not signed", config.admin);
+        return Err(DataVersionError::Unauthorized.into());
+    }
+    config.apply(update)?;
+    ProgramConfig::pack(config, &mut config_data)
+}
+
+/// Returns the data versions this program supports
+fn get_version_info() -> ProgramResult {
+    set_return_data(&VersionInfo::default().try_to_vec()?);
+    Ok(())
+}
+
 /// Main processing entry point dispatches to specific
 /// instruction handlers
 pub fn process(
@@ -82,20 +797,141 @@ pub fn process(
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    msg!("Received process request 0.2.0");
-    // Check the account for program relationship
-    if let Err(error) = check_account_ownership(program_id, accounts) {
-        return Err(error);
-    };
+    log_debug!("Received process request {}", PROGRAM_VERSION);
     // Unpack the inbound data, mapping instruction to appropriate structure
-    msg!("Attempting to unpack");
+    log_debug!("Attempting to unpack");
     let instruction = VersionProgramInstruction::unpack(instruction_data)?;
+    let specs = instruction.account_specs();
+    // Derived once for the account checks and the handlers
+    let config_address = specs
+        .expects_address(AccountAddress::Config)
+        .then(|| find_config_address(program_id));
+    validate_accounts(program_id, config_address.as_ref(), accounts, &specs)?;
+    // Take the config account out of the accounts of instructions that read it
+    let (config, accounts) = if instruction.reads_config() {
+        let config_account = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        (
+            load_config(program_id, config_account)?,
+            [&accounts[..1], &accounts[2..]].concat(),
+        )
+    } else {
+        (ProgramConfig::default(), accounts.to_vec())
+    };
+    let accounts = accounts.as_slice();
     match instruction {
-        VersionProgramInstruction::InitializeAccount => initialize_account(accounts),
-        VersionProgramInstruction::SetU64Value(value) => set_u64_value(accounts, value),
-        VersionProgramInstruction::SetString(value) => set_string_value(accounts, value),
+        VersionProgramInstruction::InitializeAccount(args) => {
+            initialize_account(program_id, accounts, args)
+        }
+        VersionProgramInstruction::SetU64Value(value) => {
+            update_account(accounts, &config, vec![AccountUpdate::SetU64Value(value)])
+        }
+        VersionProgramInstruction::SetString(value) => {
+            update_account(accounts, &config, vec![AccountUpdate::SetString(value)])
+        }
+        VersionProgramInstruction::SetAuthority(authority) => update_account(
+            accounts,
+            &config,
+            vec![AccountUpdate::SetAuthority(authority)],
+        ),
+        VersionProgramInstruction::CompareAndSetU64 {
+            expected_seq,
+            value,
+        } => update_account(
+            accounts,
+            &config,
+            vec![AccountUpdate::CompareAndSetU64 {
+                expected_seq,
+                value,
+            }],
+        ),
+        VersionProgramInstruction::CompareAndSetString {
+            expected_seq,
+            value,
+        } => update_account(
+            accounts,
+            &config,
+            vec![AccountUpdate::CompareAndSetString {
+                expected_seq,
+                value,
+            }],
+        ),
+        VersionProgramInstruction::Batch(updates) => {
+            log_info!("Batch of {} updates", updates.len());
+            update_account(accounts, &config, updates)
+        }
+        VersionProgramInstruction::MigrateAccount => migrate_account(accounts, &config),
+        VersionProgramInstruction::GetState => get_state(accounts, &config),
+        VersionProgramInstruction::GetVersionInfo => get_version_info(),
+        VersionProgramInstruction::FreezeAccount => change_lifecycle(
+            accounts,
+            &config,
+            &[AccountLifecycle::Active, AccountLifecycle::PendingMigration],
+            AccountLifecycle::Frozen,
+        ),
+        VersionProgramInstruction::ThawAccount => change_lifecycle(
+            accounts,
+            &config,
+            &[AccountLifecycle::Frozen],
+            AccountLifecycle::Active,
+        ),
+        VersionProgramInstruction::CloseAccount => close_account(accounts, &config),
+        VersionProgramInstruction::InitializeConfig => {
+            let (_, bump_seed) = config_address.ok_or(ProgramError::InvalidSeeds)?;
+            initialize_config(program_id, accounts, bump_seed)
+        }
+        VersionProgramInstruction::UpdateConfig(update) => update_config(accounts, update),
+        VersionProgramInstruction::ForceMigrate => {
+            admin::force_migrate(program_id, accounts, &config)
+        }
+        VersionProgramInstruction::RewriteHeader {
+            lifecycle,
+            data_version,
+        } => admin::rewrite_header(program_id, accounts, lifecycle, data_version),
+        VersionProgramInstruction::SetConfig(update) => {
+            admin::set_config(program_id, accounts, update)
+        }
+        VersionProgramInstruction::ScheduleMigration {
+            target_version,
+            activation_slot,
+        } => admin::schedule_migration(
+            program_id,
+            accounts,
+            ScheduledMigration {
+                target_version,
+                activation_slot,
+            },
+        ),
+        VersionProgramInstruction::AddU64 { amount, bounds } => update_account(
+            accounts,
+            &config,
+            vec![AccountUpdate::AddU64 { amount, bounds }],
+        ),
+        VersionProgramInstruction::SubU64 { amount, bounds } => update_account(
+            accounts,
+            &config,
+            vec![AccountUpdate::SubU64 { amount, bounds }],
+        ),
+        VersionProgramInstruction::IncrementU64(bounds) => update_account(
+            accounts,
+            &config,
+            vec![AccountUpdate::AddU64 { amount: 1, bounds }],
+        ),
+        VersionProgramInstruction::SetField { key, value } => update_account(
+            accounts,
+            &config,
+            vec![AccountUpdate::SetField { key, value }],
+        ),
+        VersionProgramInstruction::RemoveField(key) => {
+            update_account(accounts, &config, vec![AccountUpdate::RemoveField(key)])
+        }
+        VersionProgramInstruction::SetU64ValueSigned { value, sequence } => {
+            set_signed_value(accounts, &config, SignedField::SomeValue(value), sequence)
+        }
+        VersionProgramInstruction::SetStringSigned { value, sequence } => {
+            set_signed_value(accounts, &config, SignedField::SomeString(value), sequence)
+        }
         _ => {
-            msg!("Received unknown instruction");
+            log_error!("Received unknown instruction");
             Err(DataVersionError::InvalidInstruction.into())
         }
     }
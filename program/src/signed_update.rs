@@ -0,0 +1,89 @@
+//! Updates signed off-chain by the account authority, verified through
+//! the Ed25519 program instructions of the same transaction
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    ed25519_program,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+
+/// Content field and value of a signed update
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq)]
+pub enum SignedField {
+    SomeValue(u64),
+    SomeString(String),
+}
+
+/// Message the authority signs. The sequence must equal the write
+/// sequence of the account, so a signed update applies at most once
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq)]
+pub struct SignedUpdate {
+    pub account: Pubkey,
+    pub field: SignedField,
+    pub sequence: u64,
+}
+
+impl SignedUpdate {
+    /// Bytes the authority signs with an Ed25519 program instruction
+    pub fn message(&self) -> Result<Vec<u8>, ProgramError> {
+        Ok(self.try_to_vec()?)
+    }
+}
+
+/// Size of the signature count and padding preceding the offsets
+const SIGNATURE_OFFSETS_START: usize = 2;
+/// Size of the offsets of each signature
+const SIGNATURE_OFFSETS_SIZE: usize = 14;
+/// Instruction index meaning the data is in the Ed25519 instruction itself
+const THIS_INSTRUCTION: u16 = u16::MAX;
+
+/// Reads the little endian u16 at `offset`
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+/// Keys whose signatures of `message` the Ed25519 instruction data holds.
+/// Only signatures whose key and message are within the instruction are
+/// counted. The runtime has verified every signature before the program runs
+fn ed25519_signed_keys(data: &[u8], message: &[u8]) -> Vec<Pubkey> {
+    let count = data.first().copied().unwrap_or(0) as usize;
+    (0..count)
+        .filter_map(|i| {
+            let start = SIGNATURE_OFFSETS_START + i * SIGNATURE_OFFSETS_SIZE;
+            let public_key_offset = read_u16(data, start + 4)? as usize;
+            let public_key_index = read_u16(data, start + 6)?;
+            let message_offset = read_u16(data, start + 8)? as usize;
+            let message_size = read_u16(data, start + 10)? as usize;
+            let message_index = read_u16(data, start + 12)?;
+            if public_key_index != THIS_INSTRUCTION || message_index != THIS_INSTRUCTION {
+                return None;
+            }
+            if data.get(message_offset..message_offset + message_size)? != message {
+                return None;
+            }
+            let public_key = data.get(public_key_offset..public_key_offset + 32)?;
+            Some(Pubkey::new(public_key))
+        })
+        .collect()
+}
+
+/// Keys that signed `message` in Ed25519 program instructions preceding
+/// the current instruction, read from the instructions sysvar
+pub fn ed25519_signers(
+    instructions_sysvar: &AccountInfo,
+    message: &[u8],
+) -> Result<Vec<Pubkey>, ProgramError> {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    let mut signers = Vec::new();
+    for index in 0..current_index {
+        let instruction = load_instruction_at_checked(index as usize, instructions_sysvar)?;
+        if instruction.program_id == ed25519_program::id() {
+            signers.extend(ed25519_signed_keys(&instruction.data, message));
+        }
+    }
+    Ok(signers)
+}
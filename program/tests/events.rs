@@ -0,0 +1,157 @@
+//! event emission tests, in their own process as they replace the syscall
+//! stubs every program test shares
+
+use solana_data_versioning::{
+    account_state::{ProgramAccountState, ACCOUNT_STATE_SPACE, DATA_VERSION},
+    entry_point::process_instruction,
+    events::ProgramEvent,
+    instruction::{initialize_account, set_string_value, set_u64_value},
+};
+use solana_program::{
+    account_info::AccountInfo,
+    borsh::try_from_slice_unchecked,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use solana_program_test::{processor, tokio, ProgramTest};
+use solana_sdk::{account::Account, pubkey, signer::Signer, transaction::Transaction};
+use std::sync::{Arc, Mutex};
+
+const PROGRAM_ID: Pubkey = pubkey!("PWDnx8LkjJUn9bAVzG6Fp6BuvB41x7DkBZdo9YLMGcc");
+
+/// Stand in while the program test stubs are swapped for DataLogStubs
+struct Swapping;
+
+impl SyscallStubs for Swapping {}
+
+/// Syscall stubs that record the data programs log, as native processing
+/// prints it rather than adding it to the transaction logs. Everything
+/// else is left to the program test stubs
+struct DataLogStubs {
+    stubs: Box<dyn SyscallStubs>,
+    data_logs: Arc<Mutex<Vec<Vec<Vec<u8>>>>>,
+}
+
+impl SyscallStubs for DataLogStubs {
+    fn sol_log(&self, message: &str) {
+        self.stubs.sol_log(message)
+    }
+    fn sol_log_compute_units(&self) {
+        self.stubs.sol_log_compute_units()
+    }
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.stubs
+            .sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.stubs.sol_get_clock_sysvar(var_addr)
+    }
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.stubs.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.stubs.sol_get_fees_sysvar(var_addr)
+    }
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.stubs.sol_get_rent_sysvar(var_addr)
+    }
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.stubs.sol_get_return_data()
+    }
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.stubs.sol_set_return_data(data)
+    }
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        self.data_logs
+            .lock()
+            .unwrap()
+            .push(fields.iter().map(|field| field.to_vec()).collect());
+    }
+}
+
+/// Decodes the events among the recorded data
+fn events(data_logs: &Mutex<Vec<Vec<Vec<u8>>>>) -> Vec<ProgramEvent> {
+    data_logs
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|fields| {
+            let fields = fields.iter().map(Vec::as_slice).collect::<Vec<_>>();
+            ProgramEvent::from_log_data(&fields)
+        })
+        .collect()
+}
+
+#[tokio::test]
+/// Validates updates emit the data version and write sequence they stored
+async fn test_content_set_event_pass() {
+    let account_pubkey = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new(
+        "solana_data_versioning",
+        PROGRAM_ID,
+        processor!(process_instruction),
+    );
+    program_test.add_account(
+        account_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(ACCOUNT_STATE_SPACE),
+            data: vec![0_u8; ACCOUNT_STATE_SPACE],
+            owner: PROGRAM_ID,
+            ..Account::default()
+        },
+    );
+    // Starting installs the program test stubs, which are then wrapped
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let data_logs = Arc::new(Mutex::new(Vec::new()));
+    let stubs = set_syscall_stubs(Box::new(Swapping));
+    set_syscall_stubs(Box::new(DataLogStubs {
+        stubs,
+        data_logs: data_logs.clone(),
+    }));
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            initialize_account(&PROGRAM_ID, &account_pubkey, 1, String::new(), None),
+            set_u64_value(&PROGRAM_ID, &account_pubkey, &[], 2),
+            set_string_value(&PROGRAM_ID, &account_pubkey, &[], String::from("Goober")),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = try_from_slice_unchecked::<ProgramAccountState>(&acc.data).unwrap();
+    assert_eq!(
+        events(&data_logs),
+        vec![
+            ProgramEvent::AccountInitialized {
+                account: account_pubkey,
+                data_version: DATA_VERSION,
+            },
+            ProgramEvent::ContentSet {
+                account: account_pubkey,
+                data_version: DATA_VERSION,
+                write_sequence: state.write_sequence() - 1,
+            },
+            ProgramEvent::ContentSet {
+                account: account_pubkey,
+                data_version: DATA_VERSION,
+                write_sequence: state.write_sequence(),
+            },
+        ]
+    );
+}
@@ -1,13 +1,41 @@
 //! test processor framework
 
+use borsh::{BorshDeserialize, BorshSerialize};
 use solana_data_versioning::{
-    account_state::ACCOUNT_STATE_SPACE, entry_point::process_instruction,
-    instruction::VersionProgramInstruction,
+    account_state::{
+        find_account_address, AccountAuthority, AccountContentCurrent, AccountLifecycle,
+        FieldDefaults, FieldValue, ProgramAccountState, VersionInfo, ACCOUNT_STATE_SPACE,
+        DATA_VERSION, MAX_FIELD_KEY_LEN, MAX_FIELD_STRING_LEN,
+    },
+    admin::find_program_data_address,
+    config::{find_config_address, ConfigUpdate, ProgramConfig, ScheduledMigration, CONFIG_SPACE},
+    entry_point::process_instruction,
+    error::DataVersionError,
+    events::{parse_logs, ProgramEvent, EVENT_TAG},
+    instruction::{
+        add_u64, batch, close_account, compare_and_set_string, compare_and_set_u64, force_migrate,
+        freeze_account, get_state, get_version_info, increment_u64, initialize_account,
+        initialize_config, initialize_derived_account, migrate_account, remove_field,
+        rewrite_header, schedule_migration, set_authority, set_config, set_field,
+        set_string_signed, set_string_value, set_u64_value, set_u64_value_signed, sub_u64,
+        thaw_account, update_config, AccountUpdate, ValueBounds, VersionProgramInstruction,
+        MAX_BATCH_LEN,
+    },
+    signed_update::{SignedField, SignedUpdate},
 };
 use solana_program::{
+    account_info::AccountInfo,
+    borsh::try_from_slice_unchecked,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    entrypoint::ProgramResult,
     hash::Hash,
     instruction::{AccountMeta, Instruction},
+    program::{get_return_data, invoke},
+    program_error::ProgramError,
+    program_pack::Pack,
     pubkey::Pubkey,
+    rent::Rent,
+    system_program,
 };
 use solana_program_test::{
     processor,
@@ -15,12 +43,54 @@ use solana_program_test::{
     BanksClient, ProgramTest,
 };
 use solana_sdk::{
-    account::Account, pubkey, signature::Keypair, signer::Signer, transaction::Transaction,
+    account::Account,
+    ed25519_instruction::new_ed25519_instruction,
+    instruction::InstructionError,
+    pubkey,
+    signature::Keypair,
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
     transport::TransportError,
 };
 use std::time::Duration;
 
 const PROGRAM_ID: Pubkey = pubkey!("PWDnx8LkjJUn9bAVzG6Fp6BuvB41x7DkBZdo9YLMGcc");
+const READER_ID: Pubkey = pubkey!("ReaderXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX");
+
+/// Reader instruction: forward `request` data to the program and compare
+/// the return data with `expected`
+#[derive(BorshDeserialize, BorshSerialize)]
+struct ReaderInstruction {
+    request: Vec<u8>,
+    expected: Vec<u8>,
+}
+
+/// Test program that reads this program's return data through CPI, as
+/// BanksClient does not surface return data.
+/// accounts[0] is this program, the remainder are forwarded
+fn process_reader(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let reader = ReaderInstruction::try_from_slice(instruction_data)?;
+    let metas = accounts[1..]
+        .iter()
+        .map(|account| AccountMeta {
+            pubkey: *account.key,
+            is_signer: account.is_signer,
+            is_writable: account.is_writable,
+        })
+        .collect();
+    invoke(
+        &Instruction::new_with_bytes(PROGRAM_ID, &reader.request, metas),
+        accounts,
+    )?;
+    match get_return_data() {
+        Some((program_id, data)) if program_id == PROGRAM_ID && data == reader.expected => Ok(()),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
 
 /// Sets up the Program test and initializes 'n' program_accounts
 async fn setup(program_accounts: &[Pubkey]) -> (BanksClient, Keypair, Hash) {
@@ -30,12 +100,13 @@ async fn setup(program_accounts: &[Pubkey]) -> (BanksClient, Keypair, Hash) {
         PROGRAM_ID,
         processor!(process_instruction), // Run the native version with `cargo test`
     );
+    program_test.add_program("reader", READER_ID, processor!(process_reader));
     solana_logger::setup_with("solana_program_test=debug");
     for account in program_accounts {
         program_test.add_account(
             *account,
             Account {
-                lamports: 5,
+                lamports: Rent::default().minimum_balance(ACCOUNT_STATE_SPACE),
                 data: vec![0_u8; ACCOUNT_STATE_SPACE],
                 owner: PROGRAM_ID,
                 ..Account::default()
@@ -45,20 +116,49 @@ async fn setup(program_accounts: &[Pubkey]) -> (BanksClient, Keypair, Hash) {
     program_test.start().await
 }
 
-/// Submit transaction with relevant instruction data
-#[allow(clippy::ptr_arg)]
+/// Submit transaction with relevant instruction
 async fn submit_txn(
-    instruction_data: &VersionProgramInstruction,
-    accounts: &[AccountMeta],
+    instruction: Instruction,
+    payer: &dyn Signer,
+    recent_blockhash: Hash,
+    banks_client: &mut BanksClient,
+) -> Result<(), TransportError> {
+    submit_signed_txn(instruction, payer, &[], recent_blockhash, banks_client).await
+}
+
+/// Submit transaction with relevant instruction and additional signers
+async fn submit_signed_txn(
+    instruction: Instruction,
     payer: &dyn Signer,
+    signers: &[&dyn Signer],
+    recent_blockhash: Hash,
+    banks_client: &mut BanksClient,
+) -> Result<(), TransportError> {
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    banks_client.process_transaction(transaction).await
+}
+
+/// Submit `request` through the reader, expecting `expected` as return data
+async fn submit_read_txn(
+    request: Instruction,
+    expected: Vec<u8>,
+    payer: &Keypair,
     recent_blockhash: Hash,
     banks_client: &mut BanksClient,
 ) -> Result<(), TransportError> {
+    let mut metas = vec![AccountMeta::new_readonly(PROGRAM_ID, false)];
+    metas.extend(request.accounts);
     let mut transaction = Transaction::new_with_payer(
         &[Instruction::new_with_borsh(
-            PROGRAM_ID,
-            instruction_data,
-            accounts.to_vec(),
+            READER_ID,
+            &ReaderInstruction {
+                request: request.data,
+                expected,
+            },
+            metas,
         )],
         Some(&payer.pubkey()),
     );
@@ -66,6 +166,11 @@ async fn submit_txn(
     banks_client.process_transaction(transaction).await
 }
 
+/// Decodes current version account state from the account data
+fn unpack_state(account: &Account) -> ProgramAccountState {
+    try_from_slice_unchecked::<ProgramAccountState>(&account.data).unwrap()
+}
+
 #[tokio::test]
 /// Validates initialization processing
 async fn test_initialize_prechange_pass() {
@@ -88,10 +193,8 @@ async fn test_initialize_prechange_pass() {
 
     // Initialize account
     println!("Recent BH {:?}", recent_blockhash);
-    let macc = [AccountMeta::new(account_pubkey, false)];
     let result = submit_txn(
-        &VersionProgramInstruction::InitializeAccount,
-        &macc,
+        initialize_account(&PROGRAM_ID, &account_pubkey, 1, String::new(), None),
         &payer,
         recent_blockhash,
         &mut banks_client,
@@ -106,8 +209,8 @@ async fn test_initialize_prechange_pass() {
         .unwrap()
         .unwrap();
     assert_eq!(acc.data[0], 1);
-    assert_eq!(acc.data[1], 1);
-    assert_eq!(acc.data[2], 1);
+    assert_eq!(acc.data[1], DATA_VERSION);
+    assert_eq!(unpack_state(&acc).content().somevalue, 1u64);
 
     // Wait for new blockhash
     tokio::time::sleep(Duration::from_millis(500)).await;
@@ -115,8 +218,7 @@ async fn test_initialize_prechange_pass() {
     println!("New BH {:?}", new_blockhash);
     // Initialize account twice fail
     let bad_result = submit_txn(
-        &VersionProgramInstruction::InitializeAccount,
-        &macc,
+        initialize_account(&PROGRAM_ID, &account_pubkey, 1, String::new(), None),
         &payer,
         new_blockhash,
         &mut banks_client,
@@ -130,8 +232,7 @@ async fn test_initialize_prechange_pass() {
     println!("New2 BH {:?}", new2_blockhash);
     // Initialize account twice fail
     let set_result = submit_txn(
-        &VersionProgramInstruction::SetU64Value(50u64),
-        &macc,
+        set_u64_value(&PROGRAM_ID, &account_pubkey, &[], 50u64),
         &payer,
         new2_blockhash,
         &mut banks_client,
@@ -145,16 +246,15 @@ async fn test_initialize_prechange_pass() {
         .unwrap()
         .unwrap();
     assert_eq!(acc.data[0], 1);
-    assert_eq!(acc.data[1], 1);
-    assert_eq!(acc.data[2], 50u8);
+    assert_eq!(acc.data[1], DATA_VERSION);
+    assert_eq!(unpack_state(&acc).content().somevalue, 50u64);
 
     tokio::time::sleep(Duration::from_millis(500)).await;
     let new3_blockhash = banks_client.get_latest_blockhash().await.unwrap();
     println!("New3 BH {:?}", new3_blockhash);
     // Initialize account twice fail
     let set_result = submit_txn(
-        &VersionProgramInstruction::SetString(String::from("Goober")),
-        &macc,
+        set_string_value(&PROGRAM_ID, &account_pubkey, &[], String::from("Goober")),
         &payer,
         new3_blockhash,
         &mut banks_client,
@@ -168,8 +268,8 @@ async fn test_initialize_prechange_pass() {
         .unwrap()
         .unwrap();
     assert_eq!(acc.data[0], 1);
-    assert_eq!(acc.data[1], 1);
-    assert_eq!(acc.data[2], 50u8);
+    assert_eq!(acc.data[1], DATA_VERSION);
+    assert_eq!(unpack_state(&acc).content().somevalue, 50u64);
     println!("{:?}", acc.data);
 }
 
@@ -181,12 +281,2111 @@ async fn test_unknown_instruction_error_pass() {
     let (mut banks_client, payer, recent_blockhash) = setup(&[account_pubkey]).await;
     // Initialize account
     let result = submit_txn(
-        &VersionProgramInstruction::FailInstruction,
-        &[AccountMeta::new(account_pubkey, false)],
+        Instruction::new_with_borsh(
+            PROGRAM_ID,
+            &VersionProgramInstruction::FailInstruction,
+            vec![AccountMeta::new(account_pubkey, false)],
+        ),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_err());
+
+    // Malformed instruction data is an error rather than a panic
+    let result = submit_txn(
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &[255, 1, 2],
+            vec![AccountMeta::new(account_pubkey, false)],
+        ),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(DataVersionError::DeserializationFailure as u32)
+        )
+    );
+}
+
+#[tokio::test]
+/// Validates multisig authority gating of account mutations
+async fn test_multisig_authority_pass() {
+    // Setup runtime testing and accounts
+    let account_pubkey = Pubkey::new_unique();
+    let (mut banks_client, payer, recent_blockhash) = setup(&[account_pubkey]).await;
+    let signer1 = Keypair::new();
+    let signer2 = Keypair::new();
+    let signer3 = Keypair::new();
+    let result = submit_txn(
+        initialize_account(&PROGRAM_ID, &account_pubkey, 1, String::new(), None),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // Unreachable threshold is rejected
+    let result = submit_txn(
+        set_authority(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &[],
+            AccountAuthority::Multisig {
+                threshold: 4,
+                signers: vec![signer1.pubkey(), signer2.pubkey(), signer3.pubkey()],
+            },
+        ),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_err());
+
+    // Establish a 2 of 3 multisig
+    let authority = AccountAuthority::Multisig {
+        threshold: 2,
+        signers: vec![signer1.pubkey(), signer2.pubkey(), signer3.pubkey()],
+    };
+    let result = submit_txn(
+        set_authority(&PROGRAM_ID, &account_pubkey, &[], authority.clone()),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(unpack_state(&acc).authority(), &authority);
+
+    // One signer is not enough
+    let result = submit_signed_txn(
+        set_u64_value(&PROGRAM_ID, &account_pubkey, &[&signer1.pubkey()], 50u64),
+        &payer,
+        &[&signer1],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_err());
+
+    // The same signer listed twice is still one signer
+    let result = submit_signed_txn(
+        set_u64_value(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &[&signer1.pubkey(), &signer1.pubkey()],
+            50u64,
+        ),
+        &payer,
+        &[&signer1],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_err());
+
+    // Two of three succeeds
+    let result = submit_signed_txn(
+        set_u64_value(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &[&signer1.pubkey(), &signer3.pubkey()],
+            50u64,
+        ),
+        &payer,
+        &[&signer1, &signer3],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(unpack_state(&acc).content().somevalue, 50u64);
+}
+
+/// Sets up the Program test with a single DATA_VERSION 0 account holding
+/// `somevalue`
+async fn setup_v0_account(account_pubkey: Pubkey, somevalue: u64) -> (BanksClient, Keypair, Hash) {
+    let mut program_test = ProgramTest::new(
+        "solana_data_versioning",
+        PROGRAM_ID,
+        processor!(process_instruction),
+    );
+    program_test.add_program("reader", READER_ID, processor!(process_reader));
+    // DATA_VERSION 0 layout: initialized, version, somevalue
+    let mut data = vec![0_u8; ACCOUNT_STATE_SPACE];
+    data[0] = 1;
+    data[2..10].copy_from_slice(&somevalue.to_le_bytes());
+    program_test.add_account(
+        account_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(ACCOUNT_STATE_SPACE),
+            data,
+            owner: PROGRAM_ID,
+            ..Account::default()
+        },
+    );
+    program_test.start().await
+}
+
+#[tokio::test]
+/// Validates explicit migration of backlevel account data
+async fn test_migrate_account_pass() {
+    let account_pubkey = Pubkey::new_unique();
+    let (mut banks_client, payer, recent_blockhash) = setup_v0_account(account_pubkey, 25).await;
+
+    let result = submit_txn(
+        migrate_account(&PROGRAM_ID, &account_pubkey, &[]),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(acc.data[0], 1);
+    assert_eq!(acc.data[1], DATA_VERSION);
+    let state = unpack_state(&acc);
+    assert_eq!(state.authority(), &AccountAuthority::None);
+    assert_eq!(state.content().somevalue, 25u64);
+    assert_eq!(state.content().somestring, "");
+}
+
+#[tokio::test]
+/// Validates the read only return data instructions
+async fn test_return_data_pass() {
+    let account_pubkey = Pubkey::new_unique();
+    let (mut banks_client, payer, recent_blockhash) = setup_v0_account(account_pubkey, 25).await;
+
+    // Version information needs no accounts
+    let result = submit_read_txn(
+        get_version_info(&PROGRAM_ID),
+        VersionInfo {
+            data_version: DATA_VERSION,
+            oldest_supported_version: 0,
+            program_version: String::from("0.2.0"),
+        }
+        .try_to_vec()
+        .unwrap(),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // State is returned at the current version
+    let expected = AccountContentCurrent {
+        somevalue: 25,
+        ..AccountContentCurrent::default()
+    };
+    let result = submit_read_txn(
+        get_state(&PROGRAM_ID, &account_pubkey),
+        expected.try_to_vec().unwrap(),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // Reading does not migrate the stored data
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(acc.data[1], 0);
+
+    // Mismatched content is detected by the reader
+    let result = submit_read_txn(
+        get_state(&PROGRAM_ID, &account_pubkey),
+        AccountContentCurrent::default().try_to_vec().unwrap(),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+/// Validates write sequence tracking and compare and set updates
+async fn test_compare_and_set_pass() {
+    let account_pubkey = Pubkey::new_unique();
+    let (mut banks_client, payer, recent_blockhash) = setup(&[account_pubkey]).await;
+    let result = submit_txn(
+        initialize_account(&PROGRAM_ID, &account_pubkey, 1, String::new(), None),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(unpack_state(&acc).write_sequence(), 1);
+
+    // Matching sequence writes and advances the sequence
+    let result = submit_txn(
+        compare_and_set_u64(&PROGRAM_ID, &account_pubkey, &[], 1, 50),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = unpack_state(&acc);
+    assert_eq!(state.write_sequence(), 2);
+    assert_eq!(state.content().somevalue, 50);
+
+    // A stale sequence is rejected
+    let result = submit_txn(
+        compare_and_set_string(&PROGRAM_ID, &account_pubkey, &[], 1, String::from("Goober")),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(DataVersionError::SequenceMismatch as u32)
+        )
+    );
+
+    let result = submit_txn(
+        compare_and_set_string(&PROGRAM_ID, &account_pubkey, &[], 2, String::from("Goober")),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = unpack_state(&acc);
+    assert_eq!(state.write_sequence(), 3);
+    assert_eq!(state.content().somestring, "Goober");
+}
+
+#[tokio::test]
+/// Validates batched updates are written together or not at all
+async fn test_batch_pass() {
+    let account_pubkey = Pubkey::new_unique();
+    let (mut banks_client, payer, recent_blockhash) = setup(&[account_pubkey]).await;
+    let result = submit_txn(
+        initialize_account(&PROGRAM_ID, &account_pubkey, 1, String::new(), None),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // Both values set with a single write
+    let result = submit_txn(
+        batch(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &[],
+            vec![
+                AccountUpdate::CompareAndSetU64 {
+                    expected_seq: 1,
+                    value: 50,
+                },
+                AccountUpdate::SetString(String::from("Goober")),
+            ],
+        ),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = unpack_state(&acc);
+    assert_eq!(state.write_sequence(), 2);
+    assert_eq!(state.content().somevalue, 50);
+    assert_eq!(state.content().somestring, "Goober");
+
+    // A failing update discards the earlier ones
+    let result = submit_txn(
+        batch(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &[],
+            vec![
+                AccountUpdate::SetU64Value(75),
+                AccountUpdate::CompareAndSetString {
+                    expected_seq: 1,
+                    value: String::from("Stale"),
+                },
+            ],
+        ),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_err());
+
+    // Oversized batches are rejected
+    let result = submit_txn(
+        batch(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &[],
+            (0..=MAX_BATCH_LEN as u64)
+                .map(AccountUpdate::SetU64Value)
+                .collect(),
+        ),
         &payer,
         recent_blockhash,
         &mut banks_client,
     )
     .await;
     assert!(result.is_err());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = unpack_state(&acc);
+    assert_eq!(state.write_sequence(), 2);
+    assert_eq!(state.content().somevalue, 50);
+    assert_eq!(state.content().somestring, "Goober");
+}
+
+#[tokio::test]
+/// Validates initialization from clients that predate initialization arguments
+async fn test_initialize_legacy_pass() {
+    let account_pubkey = Pubkey::new_unique();
+    let (mut banks_client, payer, recent_blockhash) = setup(&[account_pubkey]).await;
+    let result = submit_txn(
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &[0],
+            vec![AccountMeta::new(account_pubkey, false)],
+        ),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = unpack_state(&acc);
+    assert!(state.initialized());
+    assert_eq!(state.bump_seed(), None);
+    assert_eq!(state.content().somevalue, 1u64);
+}
+
+#[tokio::test]
+/// Validates the authority set when initializing, and initialization from
+/// clients that predate the initial authority
+async fn test_initialize_authority_pass() {
+    let account_pubkey = Pubkey::new_unique();
+    let v4_account_pubkey = Pubkey::new_unique();
+    let (mut banks_client, payer, recent_blockhash) =
+        setup(&[account_pubkey, v4_account_pubkey]).await;
+    let authority = Keypair::new();
+
+    // An unreachable threshold is rejected
+    let result = submit_txn(
+        initialize_account(
+            &PROGRAM_ID,
+            &account_pubkey,
+            1,
+            String::new(),
+            Some(AccountAuthority::Multisig {
+                threshold: 2,
+                signers: vec![authority.pubkey()],
+            }),
+        ),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_err());
+
+    let result = submit_txn(
+        initialize_account(
+            &PROGRAM_ID,
+            &account_pubkey,
+            1,
+            String::new(),
+            Some(AccountAuthority::Single(authority.pubkey())),
+        ),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        unpack_state(&acc).authority(),
+        &AccountAuthority::Single(authority.pubkey())
+    );
+
+    // No one else can update the account or take it over
+    let result = submit_txn(
+        set_authority(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &[],
+            AccountAuthority::Single(payer.pubkey()),
+        ),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(DataVersionError::Unauthorized as u32)
+        )
+    );
+    let result = submit_signed_txn(
+        set_u64_value(&PROGRAM_ID, &account_pubkey, &[&authority.pubkey()], 5),
+        &payer,
+        &[&authority],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // Arguments without the authority field leave the account open
+    let mut data = vec![0];
+    data.extend(50u64.to_le_bytes());
+    data.extend(String::from("Goober").try_to_vec().unwrap());
+    data.extend(None::<String>.try_to_vec().unwrap());
+    let result = submit_txn(
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![AccountMeta::new(v4_account_pubkey, false)],
+        ),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(v4_account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = unpack_state(&acc);
+    assert_eq!(state.authority(), &AccountAuthority::None);
+    assert_eq!(state.content().somevalue, 50u64);
+    assert_eq!(state.content().somestring, "Goober");
+}
+
+#[tokio::test]
+/// Validates program derived account checks ahead of creation
+async fn test_initialize_derived_account_fail() {
+    let (mut banks_client, payer, recent_blockhash) = setup(&[]).await;
+    let owner = Keypair::new();
+    let label = String::from("config");
+
+    // The owner must sign
+    let mut instruction = initialize_derived_account(
+        &PROGRAM_ID,
+        &owner.pubkey(),
+        &payer.pubkey(),
+        label.clone(),
+        50,
+        String::from("Goober"),
+        None,
+    );
+    instruction.accounts[1].is_signer = false;
+    let result = submit_txn(instruction, &payer, recent_blockhash, &mut banks_client).await;
+    assert!(result.is_err());
+
+    // The address must derive from the owner and label
+    let mut instruction = initialize_derived_account(
+        &PROGRAM_ID,
+        &owner.pubkey(),
+        &payer.pubkey(),
+        label,
+        50,
+        String::from("Goober"),
+        None,
+    );
+    instruction.accounts[0].pubkey = find_account_address(&PROGRAM_ID, &owner.pubkey(), "other").0;
+    let result = submit_signed_txn(
+        instruction,
+        &payer,
+        &[&owner],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_err());
+}
+
+// Native processing cannot resize account data during CPI, which creating
+// the account requires. Run with `cargo test-bpf`
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+/// Validates creation and initialization of program derived accounts
+async fn test_initialize_derived_account_pass() {
+    let (mut banks_client, payer, recent_blockhash) = setup(&[]).await;
+    let owner = Keypair::new();
+    let label = String::from("config");
+    let (account_pubkey, bump_seed) = find_account_address(&PROGRAM_ID, &owner.pubkey(), &label);
+
+    let result = submit_signed_txn(
+        initialize_derived_account(
+            &PROGRAM_ID,
+            &owner.pubkey(),
+            &payer.pubkey(),
+            label,
+            50,
+            String::from("Goober"),
+            None,
+        ),
+        &payer,
+        &[&owner],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(acc.owner, PROGRAM_ID);
+    assert_eq!(acc.data.len(), ACCOUNT_STATE_SPACE);
+    let rent = banks_client.get_rent().await.unwrap();
+    assert!(rent.is_exempt(acc.lamports, acc.data.len()));
+    let state = unpack_state(&acc);
+    assert!(state.initialized());
+    assert_eq!(state.bump_seed(), Some(bump_seed));
+    assert_eq!(state.authority(), &AccountAuthority::Single(owner.pubkey()));
+    assert_eq!(state.content().somevalue, 50u64);
+    assert_eq!(state.content().somestring, "Goober");
+}
+
+/// Sets up the program test with a system account at `address`, funded
+/// with `lamports` and `space` bytes allocated, as anyone may fund an
+/// address before the program creates it. Native processing can then
+/// create it, as it cannot resize account data during CPI. When given,
+/// the upgrade authority is recorded in the program data
+async fn setup_prefunded(
+    address: Pubkey,
+    lamports: u64,
+    space: usize,
+    upgrade_authority: Option<Pubkey>,
+) -> (BanksClient, Keypair, Hash) {
+    let mut program_test = ProgramTest::new(
+        "solana_data_versioning",
+        PROGRAM_ID,
+        processor!(process_instruction),
+    );
+    if let Some(upgrade_authority) = upgrade_authority {
+        add_program_data(&mut program_test, PROGRAM_ID, upgrade_authority);
+    }
+    program_test.add_account(
+        address,
+        Account {
+            lamports,
+            data: vec![0_u8; space],
+            owner: system_program::id(),
+            ..Account::default()
+        },
+    );
+    program_test.start().await
+}
+
+#[tokio::test]
+/// Validates creation of program derived accounts at a funded address,
+/// which belong to the owner
+async fn test_initialize_prefunded_derived_account_pass() {
+    let owner = Keypair::new();
+    let label = String::from("config");
+    let (account_pubkey, bump_seed) = find_account_address(&PROGRAM_ID, &owner.pubkey(), &label);
+    let (mut banks_client, payer, recent_blockhash) =
+        setup_prefunded(account_pubkey, 1_000, ACCOUNT_STATE_SPACE, None).await;
+
+    let result = submit_signed_txn(
+        initialize_derived_account(
+            &PROGRAM_ID,
+            &owner.pubkey(),
+            &payer.pubkey(),
+            label,
+            50,
+            String::from("Goober"),
+            None,
+        ),
+        &payer,
+        &[&owner],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(acc.owner, PROGRAM_ID);
+    assert_eq!(acc.data.len(), ACCOUNT_STATE_SPACE);
+    let rent = banks_client.get_rent().await.unwrap();
+    assert!(rent.is_exempt(acc.lamports, acc.data.len()));
+    let state = unpack_state(&acc);
+    assert!(state.initialized());
+    assert_eq!(state.bump_seed(), Some(bump_seed));
+    assert_eq!(state.authority(), &AccountAuthority::Single(owner.pubkey()));
+    assert_eq!(state.content().somevalue, 50u64);
+    assert_eq!(state.content().somestring, "Goober");
+
+    // Only the owner may update it
+    let result = submit_txn(
+        set_u64_value(&PROGRAM_ID, &account_pubkey, &[], 60),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_err());
+    let result = submit_signed_txn(
+        set_u64_value(&PROGRAM_ID, &account_pubkey, &[&owner.pubkey()], 60),
+        &payer,
+        &[&owner],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_account_lifecycle_pass() {
+    let account_pubkey = Pubkey::new_unique();
+    let destination_pubkey = Pubkey::new_unique();
+    let (mut banks_client, payer, recent_blockhash) =
+        setup(&[account_pubkey, destination_pubkey]).await;
+    let result = submit_txn(
+        initialize_account(&PROGRAM_ID, &account_pubkey, 1, String::new(), None),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // Freezing requires an authority
+    let result = submit_txn(
+        freeze_account(&PROGRAM_ID, &account_pubkey, &[]),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(DataVersionError::AuthorityRequired as u32)
+        )
+    );
+
+    let authority = payer.pubkey();
+    let result = submit_txn(
+        set_authority(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &[],
+            AccountAuthority::Single(authority),
+        ),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let result = submit_txn(
+        freeze_account(&PROGRAM_ID, &account_pubkey, &[&authority]),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(unpack_state(&acc).lifecycle(), AccountLifecycle::Frozen);
+
+    // Frozen accounts can neither be updated nor migrated
+    let frozen_error = TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(DataVersionError::AccountFrozen as u32),
+    );
+    let result = submit_txn(
+        set_u64_value(&PROGRAM_ID, &account_pubkey, &[&authority], 50),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(result.unwrap_err().unwrap(), frozen_error);
+    let result = submit_txn(
+        migrate_account(&PROGRAM_ID, &account_pubkey, &[&authority]),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    let error = result.unwrap_err().unwrap();
+    assert_eq!(error, frozen_error);
+    // Clients decode the error and its message from the transaction error
+    let decoded = DataVersionError::from_transaction_error(&error).unwrap();
+    assert_eq!(decoded, DataVersionError::AccountFrozen);
+    assert_eq!(decoded.to_string(), "Account is frozen");
+
+    let result = submit_txn(
+        thaw_account(&PROGRAM_ID, &account_pubkey, &[&authority]),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let result = submit_txn(
+        set_u64_value(&PROGRAM_ID, &account_pubkey, &[&authority], 51),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // Closing moves the lamports and the account is reclaimed
+    let result = submit_txn(
+        close_account(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &[&authority],
+            &destination_pubkey,
+        ),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    assert!(banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .is_none());
+    let destination = banks_client
+        .get_account(destination_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        destination.lamports,
+        2 * Rent::default().minimum_balance(ACCOUNT_STATE_SPACE)
+    );
+}
+
+/// Sets up v0 layout accounts with the config account already created
+/// and, when given, the upgrade authority recorded in the program data
+async fn setup_config(
+    config: ProgramConfig,
+    v0_accounts: &[Pubkey],
+    upgrade_authority: Option<Pubkey>,
+) -> (BanksClient, Keypair, Hash) {
+    program_test_with_config(config, v0_accounts, upgrade_authority)
+        .start()
+        .await
+}
+
+/// Records the upgrade authority in the program data of the program
+/// deployed at `program_id`
+fn add_program_data(program_test: &mut ProgramTest, program_id: Pubkey, upgrade_authority: Pubkey) {
+    let data = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+        slot: 0,
+        upgrade_authority_address: Some(upgrade_authority),
+    })
+    .unwrap();
+    program_test.add_account(
+        find_program_data_address(&program_id),
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: bpf_loader_upgradeable::id(),
+            ..Account::default()
+        },
+    );
+}
+
+/// Program test with v0 layout accounts, the config account and, when
+/// given, the upgrade authority recorded in the program data
+fn program_test_with_config(
+    config: ProgramConfig,
+    v0_accounts: &[Pubkey],
+    upgrade_authority: Option<Pubkey>,
+) -> ProgramTest {
+    let mut program_test = ProgramTest::new(
+        "solana_data_versioning",
+        PROGRAM_ID,
+        processor!(process_instruction),
+    );
+    program_test.add_program("reader", READER_ID, processor!(process_reader));
+    if let Some(upgrade_authority) = upgrade_authority {
+        add_program_data(&mut program_test, PROGRAM_ID, upgrade_authority);
+    }
+    let mut data = vec![0_u8; CONFIG_SPACE];
+    config.pack_into_slice(&mut data);
+    program_test.add_account(
+        find_config_address(&PROGRAM_ID).0,
+        Account {
+            lamports: Rent::default().minimum_balance(CONFIG_SPACE),
+            data,
+            owner: PROGRAM_ID,
+            ..Account::default()
+        },
+    );
+    for account in v0_accounts {
+        let mut data = vec![0_u8; ACCOUNT_STATE_SPACE];
+        data[0] = 1;
+        data[2..10].copy_from_slice(&25u64.to_le_bytes());
+        program_test.add_account(
+            *account,
+            Account {
+                lamports: Rent::default().minimum_balance(ACCOUNT_STATE_SPACE),
+                data,
+                owner: PROGRAM_ID,
+                ..Account::default()
+            },
+        );
+    }
+    program_test
+}
+
+#[tokio::test]
+async fn test_config_pass() {
+    let admin = Keypair::new();
+    let account_pubkey = Pubkey::new_unique();
+    let old_account_pubkey = Pubkey::new_unique();
+    let (mut banks_client, payer, recent_blockhash) = setup_config(
+        ProgramConfig {
+            is_initialized: true,
+            admin: admin.pubkey(),
+            migrations_paused: true,
+            field_defaults: FieldDefaults {
+                somestring: String::from("Default"),
+            },
+            ..ProgramConfig::default()
+        },
+        &[account_pubkey, old_account_pubkey],
+        None,
+    )
+    .await;
+
+    // The config can only be created once
+    let result = submit_signed_txn(
+        initialize_config(&PROGRAM_ID, &admin.pubkey(), &payer.pubkey()),
+        &payer,
+        &[&admin],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_err());
+
+    // Backlevel accounts are neither migrated nor written while paused
+    let result = submit_txn(
+        migrate_account(&PROGRAM_ID, &account_pubkey, &[]),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(DataVersionError::MigrationsPaused as u32)
+        )
+    );
+    let result = submit_txn(
+        set_u64_value(&PROGRAM_ID, &account_pubkey, &[], 50),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_err());
+
+    // Reading converts with the config defaults, as a write would store
+    let expected = AccountContentCurrent {
+        somevalue: 25,
+        somestring: String::from("Default"),
+        ..AccountContentCurrent::default()
+    };
+    let result = submit_read_txn(
+        get_state(&PROGRAM_ID, &account_pubkey),
+        expected.try_to_vec().unwrap(),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // Only the admin may update the config
+    let resume = ConfigUpdate {
+        migrations_paused: Some(false),
+        ..ConfigUpdate::default()
+    };
+    let result = submit_txn(
+        update_config(&PROGRAM_ID, &payer.pubkey(), resume.clone()),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_err());
+    let result = submit_signed_txn(
+        update_config(&PROGRAM_ID, &admin.pubkey(), resume),
+        &payer,
+        &[&admin],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // Migration gives the added field the configured default
+    let result = submit_txn(
+        // The account has no authority, the extra signer only makes the
+        // transaction differ from the paused attempt
+        migrate_account(&PROGRAM_ID, &account_pubkey, &[&payer.pubkey()]),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(acc.data[1], DATA_VERSION);
+    assert_eq!(unpack_state(&acc).content().somestring, "Default");
+
+    // Accounts below the minimum readable version are refused
+    let result = submit_signed_txn(
+        update_config(
+            &PROGRAM_ID,
+            &admin.pubkey(),
+            ConfigUpdate {
+                min_readable_version: Some(1),
+                ..ConfigUpdate::default()
+            },
+        ),
+        &payer,
+        &[&admin],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let result = submit_txn(
+        set_u64_value(&PROGRAM_ID, &old_account_pubkey, &[], 50),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(DataVersionError::VersionBelowMinimum as u32)
+        )
+    );
+    let result = submit_txn(
+        set_u64_value(&PROGRAM_ID, &account_pubkey, &[], 60),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+/// Validates that only the upgrade authority creates the config, at an
+/// address funded ahead of its creation
+async fn test_initialize_config_pass() {
+    let upgrade_authority = Keypair::new();
+    let (config_pubkey, bump_seed) = find_config_address(&PROGRAM_ID);
+    let (mut banks_client, payer, recent_blockhash) = setup_prefunded(
+        config_pubkey,
+        1_000,
+        CONFIG_SPACE,
+        Some(upgrade_authority.pubkey()),
+    )
+    .await;
+
+    // Another signer can not take the config over
+    let intruder = Keypair::new();
+    let result = submit_signed_txn(
+        initialize_config(&PROGRAM_ID, &intruder.pubkey(), &payer.pubkey()),
+        &payer,
+        &[&intruder],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(DataVersionError::NotUpgradeAuthority as u32)
+        )
+    );
+
+    let result = submit_signed_txn(
+        initialize_config(&PROGRAM_ID, &upgrade_authority.pubkey(), &payer.pubkey()),
+        &payer,
+        &[&upgrade_authority],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(config_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(acc.owner, PROGRAM_ID);
+    let rent = banks_client.get_rent().await.unwrap();
+    assert!(rent.is_exempt(acc.lamports, acc.data.len()));
+    let config = ProgramConfig::unpack(&acc.data).unwrap();
+    assert_eq!(config.admin, upgrade_authority.pubkey());
+    assert_eq!(config.bump_seed, bump_seed);
+    assert!(!config.migrations_paused);
+
+    // A minimum readable version above DATA_VERSION is refused
+    let result = submit_signed_txn(
+        update_config(
+            &PROGRAM_ID,
+            &upgrade_authority.pubkey(),
+            ConfigUpdate {
+                min_readable_version: Some(DATA_VERSION + 1),
+                ..ConfigUpdate::default()
+            },
+        ),
+        &payer,
+        &[&upgrade_authority],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+    let result = submit_signed_txn(
+        update_config(
+            &PROGRAM_ID,
+            &upgrade_authority.pubkey(),
+            ConfigUpdate {
+                min_readable_version: Some(DATA_VERSION),
+                ..ConfigUpdate::default()
+            },
+        ),
+        &payer,
+        &[&upgrade_authority],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+/// Validates a deployment at another address checks its own upgrade authority
+async fn test_initialize_config_other_deployment_pass() {
+    let program_id = Pubkey::new_unique();
+    let upgrade_authority = Keypair::new();
+    let (config_pubkey, _) = find_config_address(&program_id);
+    let mut program_test = ProgramTest::new(
+        "solana_data_versioning",
+        program_id,
+        processor!(process_instruction),
+    );
+    add_program_data(&mut program_test, program_id, upgrade_authority.pubkey());
+    program_test.add_account(
+        config_pubkey,
+        Account {
+            lamports: 1_000,
+            data: vec![0_u8; CONFIG_SPACE],
+            owner: system_program::id(),
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let result = submit_signed_txn(
+        initialize_config(&program_id, &upgrade_authority.pubkey(), &payer.pubkey()),
+        &payer,
+        &[&upgrade_authority],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(config_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(acc.owner, program_id);
+}
+
+#[tokio::test]
+async fn test_admin_pass() {
+    let upgrade_authority = Keypair::new();
+    let account_pubkey = Pubkey::new_unique();
+    let (mut banks_client, payer, recent_blockhash) = setup_config(
+        ProgramConfig {
+            is_initialized: true,
+            migrations_paused: true,
+            ..ProgramConfig::default()
+        },
+        &[account_pubkey],
+        Some(upgrade_authority.pubkey()),
+    )
+    .await;
+
+    // Only the upgrade authority may run admin instructions
+    let result = submit_txn(
+        force_migrate(&PROGRAM_ID, &account_pubkey, &payer.pubkey()),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(DataVersionError::NotUpgradeAuthority as u32)
+        )
+    );
+
+    // Forced migration ignores the pause
+    let result = submit_signed_txn(
+        force_migrate(&PROGRAM_ID, &account_pubkey, &upgrade_authority.pubkey()),
+        &payer,
+        &[&upgrade_authority],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(acc.data[1], DATA_VERSION);
+    assert_eq!(unpack_state(&acc).content().somevalue, 25u64);
+
+    // Header rewrite leaves the content in place
+    let result = submit_signed_txn(
+        rewrite_header(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &upgrade_authority.pubkey(),
+            AccountLifecycle::Frozen,
+            DATA_VERSION,
+        ),
+        &payer,
+        &[&upgrade_authority],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = unpack_state(&acc);
+    assert_eq!(state.lifecycle(), AccountLifecycle::Frozen);
+    assert_eq!(state.content().somevalue, 25u64);
+
+    // Layouts before DATA_VERSION 4 only record whether the account is
+    // initialized
+    for (lifecycle, result) in [
+        (
+            AccountLifecycle::Frozen,
+            Err(InstructionError::Custom(
+                DataVersionError::InvalidLifecycleState as u32,
+            )),
+        ),
+        (AccountLifecycle::Active, Ok(())),
+    ] {
+        let outcome = submit_signed_txn(
+            rewrite_header(
+                &PROGRAM_ID,
+                &account_pubkey,
+                &upgrade_authority.pubkey(),
+                lifecycle,
+                3,
+            ),
+            &payer,
+            &[&upgrade_authority],
+            recent_blockhash,
+            &mut banks_client,
+        )
+        .await;
+        assert_eq!(
+            outcome.map_err(|e| match e.unwrap() {
+                TransactionError::InstructionError(0, error) => error,
+                error => panic!("unexpected error {:?}", error),
+            }),
+            result
+        );
+    }
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(acc.data[..2], [1, 3]);
+
+    let result = submit_signed_txn(
+        set_config(
+            &PROGRAM_ID,
+            &upgrade_authority.pubkey(),
+            ConfigUpdate {
+                migrations_paused: Some(false),
+                ..ConfigUpdate::default()
+            },
+        ),
+        &payer,
+        &[&upgrade_authority],
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(find_config_address(&PROGRAM_ID).0)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!ProgramConfig::unpack(&acc.data).unwrap().migrations_paused);
+}
+
+#[tokio::test]
+async fn test_arithmetic_pass() {
+    let account_pubkey = Pubkey::new_unique();
+    let (mut banks_client, payer, recent_blockhash) = setup(&[account_pubkey]).await;
+    let result = submit_txn(
+        initialize_account(&PROGRAM_ID, &account_pubkey, 10, String::new(), None),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // The new value is returned
+    let result = submit_read_txn(
+        add_u64(&PROGRAM_ID, &account_pubkey, &[], 5, ValueBounds::default()),
+        15u64.try_to_vec().unwrap(),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let result = submit_read_txn(
+        increment_u64(&PROGRAM_ID, &account_pubkey, &[], ValueBounds::default()),
+        16u64.try_to_vec().unwrap(),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // Underflow and bound violations leave the value unchanged
+    let result = submit_txn(
+        sub_u64(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &[],
+            20,
+            ValueBounds::default(),
+        ),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(DataVersionError::ArithmeticOverflow as u32)
+        )
+    );
+    let result = submit_txn(
+        add_u64(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &[],
+            10,
+            ValueBounds {
+                min: None,
+                max: Some(20),
+            },
+        ),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(DataVersionError::ValueOutOfBounds as u32)
+        )
+    );
+    let result = submit_read_txn(
+        sub_u64(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &[],
+            6,
+            ValueBounds {
+                min: Some(10),
+                max: None,
+            },
+        ),
+        10u64.try_to_vec().unwrap(),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(unpack_state(&acc).content().somevalue, 10);
+}
+
+#[tokio::test]
+async fn test_fields_pass() {
+    let account_pubkey = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let (mut banks_client, payer, recent_blockhash) = setup(&[account_pubkey]).await;
+    let result = submit_txn(
+        initialize_account(&PROGRAM_ID, &account_pubkey, 1, String::new(), None),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    let fields = [
+        ("limit", FieldValue::U64(100)),
+        ("offset", FieldValue::I64(-5)),
+        ("enabled", FieldValue::Bool(true)),
+        ("name", FieldValue::String(String::from("Goober"))),
+        ("owner", FieldValue::Pubkey(owner)),
+    ];
+    for (key, value) in fields {
+        let result = submit_txn(
+            set_field(&PROGRAM_ID, &account_pubkey, &[], String::from(key), value),
+            &payer,
+            recent_blockhash,
+            &mut banks_client,
+        )
+        .await;
+        assert!(result.is_ok());
+    }
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = unpack_state(&acc);
+    assert_eq!(state.get_u64("limit"), Some(100));
+    assert_eq!(state.get_i64("offset"), Some(-5));
+    assert_eq!(state.get_bool("enabled"), Some(true));
+    assert_eq!(state.get_string("name"), Some("Goober"));
+    assert_eq!(state.get_pubkey("owner"), Some(&owner));
+    // Typed accessors do not convert
+    assert_eq!(state.get_i64("limit"), None);
+
+    let result = submit_txn(
+        set_field(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &[],
+            "k".repeat(MAX_FIELD_KEY_LEN + 1),
+            FieldValue::Bool(false),
+        ),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(DataVersionError::FieldLimitExceeded as u32)
+        )
+    );
+
+    let result = submit_txn(
+        remove_field(&PROGRAM_ID, &account_pubkey, &[], String::from("name")),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let result = submit_txn(
+        remove_field(&PROGRAM_ID, &account_pubkey, &[], String::from("missing")),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(DataVersionError::FieldNotFound as u32)
+        )
+    );
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = unpack_state(&acc);
+    assert_eq!(state.get_string("name"), None);
+    assert_eq!(state.content().fields.len(), 4);
+}
+
+#[tokio::test]
+/// Validates content that does not fit the account is refused, not truncated
+async fn test_oversized_content_fail() {
+    let account_pubkey = Pubkey::new_unique();
+    let (mut banks_client, payer, recent_blockhash) = setup(&[account_pubkey]).await;
+    let result = submit_txn(
+        initialize_account(&PROGRAM_ID, &account_pubkey, 1, String::new(), None),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // Fill most of the account with fields at their size limits
+    for index in 0..8 {
+        let result = submit_txn(
+            set_field(
+                &PROGRAM_ID,
+                &account_pubkey,
+                &[],
+                format!("{:0width$}", index, width = MAX_FIELD_KEY_LEN),
+                FieldValue::String("v".repeat(MAX_FIELD_STRING_LEN)),
+            ),
+            &payer,
+            recent_blockhash,
+            &mut banks_client,
+        )
+        .await;
+        assert!(result.is_ok());
+    }
+
+    let result = submit_txn(
+        set_string_value(&PROGRAM_ID, &account_pubkey, &[], "s".repeat(300)),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(DataVersionError::FieldLimitExceeded as u32)
+        )
+    );
+
+    // The account still decodes with its previous content
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = ProgramAccountState::unpack(&acc.data).unwrap();
+    assert_eq!(state.content().somestring, "");
+    assert_eq!(state.content().fields.len(), 8);
+}
+
+#[cfg(feature = "anchor-compat")]
+#[tokio::test]
+async fn test_anchor_compat_pass() {
+    use solana_data_versioning::anchor::instruction_data;
+
+    let account_pubkey = Pubkey::new_unique();
+    let (mut banks_client, payer, recent_blockhash) = setup(&[account_pubkey]).await;
+    let result = submit_txn(
+        initialize_account(&PROGRAM_ID, &account_pubkey, 1, String::new(), None),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // Sighash prefixed instructions are accepted
+    let mut instruction = set_u64_value(&PROGRAM_ID, &account_pubkey, &[], 0);
+    instruction.data = instruction_data(&VersionProgramInstruction::SetU64Value(42)).unwrap();
+    let result = submit_txn(instruction, &payer, recent_blockhash, &mut banks_client).await;
+    assert!(result.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(unpack_state(&acc).content().somevalue, 42);
+
+    // Accounts decode from the stored data and serialize back to it
+    let account_state = ProgramAccountState::try_deserialize(&mut &acc.data[..]).unwrap();
+    assert_eq!(account_state, unpack_state(&acc));
+    let mut serialized = Vec::new();
+    account_state.try_serialize(&mut serialized).unwrap();
+    assert_eq!(serialized, acc.data);
+    assert!(ProgramAccountState::try_deserialize(&mut &acc.data[1..]).is_err());
+}
+
+/// Ed25519 program instruction carrying the signature of `message`
+fn ed25519_instruction(signer: &Keypair, message: &[u8]) -> Instruction {
+    let keypair = ed25519_dalek::Keypair::from_bytes(&signer.to_bytes()).unwrap();
+    new_ed25519_instruction(&keypair, message)
+}
+
+#[tokio::test]
+async fn test_signed_update_pass() {
+    let account_pubkey = Pubkey::new_unique();
+    let backend = Keypair::new();
+    let (mut banks_client, payer, recent_blockhash) = setup(&[account_pubkey]).await;
+    let result = submit_txn(
+        initialize_account(&PROGRAM_ID, &account_pubkey, 1, String::new(), None),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let result = submit_txn(
+        set_authority(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &[],
+            AccountAuthority::Single(backend.pubkey()),
+        ),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // The backend signs off-chain, the payer submits
+    let message = SignedUpdate {
+        account: account_pubkey,
+        field: SignedField::SomeValue(42),
+        sequence: 2,
+    }
+    .message()
+    .unwrap();
+    let signed = ed25519_instruction(&backend, &message);
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            signed.clone(),
+            set_u64_value_signed(&PROGRAM_ID, &account_pubkey, 42, 2),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+    let acc = banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = unpack_state(&acc);
+    assert_eq!(state.content().somevalue, 42);
+    assert_eq!(state.write_sequence(), 3);
+
+    // Replaying the signature fails on the sequence
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            ed25519_instruction(&payer, &message),
+            signed,
+            set_u64_value_signed(&PROGRAM_ID, &account_pubkey, 42, 2),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            2,
+            InstructionError::Custom(DataVersionError::SequenceMismatch as u32)
+        )
+    );
+
+    // Signatures of other keys are not accepted
+    let message = SignedUpdate {
+        account: account_pubkey,
+        field: SignedField::SomeString(String::from("Goober")),
+        sequence: 3,
+    }
+    .message()
+    .unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            ed25519_instruction(&payer, &message),
+            set_string_signed(&PROGRAM_ID, &account_pubkey, String::from("Goober"), 3),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(DataVersionError::Unauthorized as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_scheduled_migration_pass() {
+    let upgrade_authority = Keypair::new();
+    let account_pubkey = Pubkey::new_unique();
+    let mut context = program_test_with_config(
+        ProgramConfig {
+            is_initialized: true,
+            ..ProgramConfig::default()
+        },
+        &[account_pubkey],
+        Some(upgrade_authority.pubkey()),
+    )
+    .start_with_context()
+    .await;
+    let payer = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+
+    // The previous layout must remain writable
+    let result = submit_signed_txn(
+        schedule_migration(
+            &PROGRAM_ID,
+            &upgrade_authority.pubkey(),
+            DATA_VERSION - 1,
+            100,
+        ),
+        &payer,
+        &[&upgrade_authority],
+        context.last_blockhash,
+        &mut context.banks_client,
+    )
+    .await;
+    assert!(result.is_err());
+    let result = submit_signed_txn(
+        schedule_migration(&PROGRAM_ID, &upgrade_authority.pubkey(), DATA_VERSION, 100),
+        &payer,
+        &[&upgrade_authority],
+        context.last_blockhash,
+        &mut context.banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = context
+        .banks_client
+        .get_account(find_config_address(&PROGRAM_ID).0)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        ProgramConfig::unpack(&acc.data)
+            .unwrap()
+            .scheduled_migration,
+        Some(ScheduledMigration {
+            target_version: DATA_VERSION,
+            activation_slot: 100,
+        })
+    );
+
+    // Before activation writes keep the previous layout, without named fields
+    let result = submit_txn(
+        set_u64_value(&PROGRAM_ID, &account_pubkey, &[], 50),
+        &payer,
+        context.last_blockhash,
+        &mut context.banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = context
+        .banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(acc.data[1], DATA_VERSION - 1);
+    assert_eq!(acc.data[12], 50u8);
+    let result = submit_txn(
+        set_field(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &[],
+            String::from("limit"),
+            FieldValue::U64(1),
+        ),
+        &payer,
+        context.last_blockhash,
+        &mut context.banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(DataVersionError::MigrationNotActive as u32)
+        )
+    );
+
+    // From the activation slot writes migrate
+    context.warp_to_slot(100).unwrap();
+    let recent_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let result = submit_txn(
+        set_field(
+            &PROGRAM_ID,
+            &account_pubkey,
+            &[],
+            String::from("limit"),
+            FieldValue::U64(1),
+        ),
+        &payer,
+        recent_blockhash,
+        &mut context.banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+    let acc = context
+        .banks_client
+        .get_account(account_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(acc.data[1], DATA_VERSION);
+    let state = unpack_state(&acc);
+    assert_eq!(state.content().somevalue, 50);
+    assert_eq!(state.get_u64("limit"), Some(1));
+}
+
+/// Log line sol_log_data writes for `fields`
+fn data_log(fields: &[&[u8]]) -> String {
+    let fields = fields.iter().map(base64::encode).collect::<Vec<_>>();
+    format!("Program data: {}", fields.join(" "))
+}
+
+#[test]
+fn test_parse_event_logs_pass() {
+    let account = Pubkey::new_unique();
+    let other_program = Pubkey::new_unique();
+    let migrated = ProgramEvent::AccountMigrated {
+        account,
+        from_version: 4,
+        to_version: DATA_VERSION,
+    };
+    let content_set = ProgramEvent::ContentSet {
+        account,
+        data_version: DATA_VERSION,
+        write_sequence: 7,
+    };
+    let closed = ProgramEvent::AccountClosed {
+        account,
+        destination: Pubkey::new_unique(),
+        lamports: 5,
+    };
+    let logs = vec![
+        format!("Program {} invoke [1]", PROGRAM_ID),
+        "Program log: Migrate account from data version 4 to 5".to_string(),
+        data_log(&[EVENT_TAG, &migrated.try_to_vec().unwrap()]),
+        // Data logged by a program this program calls is skipped
+        format!("Program {} invoke [2]", other_program),
+        data_log(&[EVENT_TAG, &closed.try_to_vec().unwrap()]),
+        format!("Program {} success", other_program),
+        // As is data this program logs that is not an event
+        data_log(&[b"not an event"]),
+        data_log(&[EVENT_TAG, &content_set.try_to_vec().unwrap()]),
+        format!(
+            "Program {} consumed 5000 of 200000 compute units",
+            PROGRAM_ID
+        ),
+        format!("Program {} success", PROGRAM_ID),
+        format!("Program {} invoke [1]", PROGRAM_ID),
+        data_log(&[EVENT_TAG, &closed.try_to_vec().unwrap()]),
+        format!("Program {} failed: custom program error: 0x1", PROGRAM_ID),
+        format!("Program {} invoke [1]", other_program),
+        data_log(&[EVENT_TAG, &migrated.try_to_vec().unwrap()]),
+        format!("Program {} success", other_program),
+    ];
+    assert_eq!(
+        parse_logs(&PROGRAM_ID, &logs),
+        vec![migrated, content_set, closed]
+    );
+}
+
+#[test]
+fn test_parse_spoofed_event_logs_fail() {
+    let account = Pubkey::new_unique();
+    let other_program = Pubkey::new_unique();
+    let closed = ProgramEvent::AccountClosed {
+        account,
+        destination: Pubkey::new_unique(),
+        lamports: 5,
+    };
+    let event = data_log(&[EVENT_TAG, &closed.try_to_vec().unwrap()]);
+    let logs = vec![
+        format!("Program {} invoke [1]", other_program),
+        // Output of the other program mimicking invocations of this one
+        format!("Program log: Program {} invoke [2]", PROGRAM_ID),
+        format!("Program return: {} success", PROGRAM_ID),
+        format!("Program {} invoke [2]", "not-a-program"),
+        format!("Program {} invoke [2] ", PROGRAM_ID),
+        event.clone(),
+        format!("Program log: Program {} success", other_program),
+        event.clone(),
+        format!("Program {} success", other_program),
+        event,
+    ];
+    assert!(parse_logs(&PROGRAM_ID, &logs).is_empty());
+}
+
+#[tokio::test]
+/// Validates the declared accounts of instructions are enforced
+async fn test_account_specs_fail() {
+    let account_pubkey = Pubkey::new_unique();
+    let (mut banks_client, payer, recent_blockhash) = setup(&[account_pubkey]).await;
+    let authority = Keypair::new();
+    let account_error = |error| TransactionError::InstructionError(0, error);
+
+    // An extra account is refused rather than passed over
+    let mut instruction = initialize_account(&PROGRAM_ID, &account_pubkey, 1, String::new(), None);
+    instruction
+        .accounts
+        .push(AccountMeta::new(Pubkey::new_unique(), false));
+    let result = submit_txn(instruction, &payer, recent_blockhash, &mut banks_client).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        account_error(InstructionError::Custom(
+            DataVersionError::UnexpectedAccount as u32
+        ))
+    );
+    let result = submit_txn(
+        initialize_account(&PROGRAM_ID, &account_pubkey, 1, String::new(), None),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert!(result.is_ok());
+
+    // The program account must be writable
+    let mut instruction = set_u64_value(&PROGRAM_ID, &account_pubkey, &[], 2);
+    instruction.accounts[0].is_writable = false;
+    let result = submit_txn(instruction, &payer, recent_blockhash, &mut banks_client).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        account_error(InstructionError::Custom(
+            DataVersionError::AccountNotWritable as u32
+        ))
+    );
+
+    // And owned by the program
+    let result = submit_txn(
+        set_u64_value(&PROGRAM_ID, &payer.pubkey(), &[], 3),
+        &payer,
+        recent_blockhash,
+        &mut banks_client,
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        account_error(InstructionError::IncorrectProgramId)
+    );
+
+    // The config account is at its derived address
+    let mut instruction = set_u64_value(&PROGRAM_ID, &account_pubkey, &[], 4);
+    instruction.accounts[1].pubkey = Pubkey::new_unique();
+    let result = submit_txn(instruction, &payer, recent_blockhash, &mut banks_client).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        account_error(InstructionError::InvalidSeeds)
+    );
+
+    // Authority accounts following the config must sign
+    let mut instruction = set_u64_value(&PROGRAM_ID, &account_pubkey, &[&authority.pubkey()], 5);
+    instruction.accounts[2].is_signer = false;
+    let result = submit_txn(instruction, &payer, recent_blockhash, &mut banks_client).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        account_error(InstructionError::MissingRequiredSignature)
+    );
+
+    // Closing takes the destination after the authority signers
+    let mut instruction = close_account(&PROGRAM_ID, &account_pubkey, &[], &payer.pubkey());
+    instruction.accounts.pop();
+    let result = submit_txn(instruction, &payer, recent_blockhash, &mut banks_client).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        account_error(InstructionError::NotEnoughAccountKeys)
+    );
+}
+
+#[tokio::test]
+/// Validates under-funded and undersized accounts are not initialized
+async fn test_initialize_rent_and_size_fail() {
+    let mut program_test = ProgramTest::new(
+        "solana_data_versioning",
+        PROGRAM_ID,
+        processor!(process_instruction),
+    );
+    let underfunded_pubkey = Pubkey::new_unique();
+    let undersized_pubkey = Pubkey::new_unique();
+    for (pubkey, lamports, len) in [
+        (underfunded_pubkey, 5, ACCOUNT_STATE_SPACE),
+        (
+            undersized_pubkey,
+            Rent::default().minimum_balance(ACCOUNT_STATE_SPACE),
+            ACCOUNT_STATE_SPACE - 1,
+        ),
+    ] {
+        program_test.add_account(
+            pubkey,
+            Account {
+                lamports,
+                data: vec![0_u8; len],
+                owner: PROGRAM_ID,
+                ..Account::default()
+            },
+        );
+    }
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    for (pubkey, error) in [
+        (underfunded_pubkey, DataVersionError::AccountNotRentExempt),
+        (undersized_pubkey, DataVersionError::AccountTooSmall),
+    ] {
+        let result = submit_txn(
+            initialize_account(&PROGRAM_ID, &pubkey, 1, String::new(), None),
+            &payer,
+            recent_blockhash,
+            &mut banks_client,
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(error as u32))
+        );
+        // No version header was written
+        let acc = banks_client.get_account(pubkey).await.unwrap().unwrap();
+        assert!(acc.data.iter().all(|byte| *byte == 0));
+    }
 }
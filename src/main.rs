@@ -1,3 +1,572 @@
-fn main() {
-    println!("Hello, world!");
+//! Operator CLI managing the accounts of the data versioning program
+
+use clap::{
+    crate_description, crate_name, crate_version, value_t_or_exit, App, AppSettings, Arg,
+    ArgMatches, SubCommand,
+};
+use serde_json::{json, Map, Value};
+use solana_clap_utils::{
+    input_parsers::{pubkey_of, pubkey_of_signer},
+    input_validators::{
+        is_parsable, is_url_or_moniker, is_valid_pubkey, is_valid_signer,
+        normalize_to_url_if_moniker,
+    },
+    keypair::signer_from_path,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_data_versioning::account_state::{AccountAuthority, FieldValue, DATA_VERSION};
+use solana_remote_wallet::remote_wallet::RemoteWalletManager;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signer};
+use std::{error::Error, process::exit, str::FromStr, sync::Arc};
+use versioning_solana_client::{DataVersionClient, RpcTransport, StoredAccount, UpdateOutcome};
+
+mod migrate;
+
+type CliResult = Result<(), Box<dyn Error>>;
+
+/// Settings resolved from the flags and the Solana CLI config
+struct Config {
+    client: DataVersionClient<RpcTransport>,
+    fee_payer: Box<dyn Signer>,
+}
+
+fn app<'a, 'b>() -> App<'a, 'b> {
+    let account_arg = Arg::with_name("account")
+        .value_name("ACCOUNT")
+        .validator(is_valid_pubkey)
+        .required(true)
+        .index(1)
+        .help("Address of the program account");
+    let authority_arg = Arg::with_name("authority")
+        .long("authority")
+        .value_name("SIGNER")
+        .validator(is_valid_signer)
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .help("Authority of the account, repeated for each multisig signer");
+    let value_args = [
+        Arg::with_name("value")
+            .long("value")
+            .value_name("U64")
+            .validator(is_parsable::<u64>)
+            .default_value("0")
+            .help("Initial somevalue"),
+        Arg::with_name("string")
+            .long("string")
+            .value_name("STRING")
+            .default_value("")
+            .help("Initial somestring"),
+        Arg::with_name("initial_authority")
+            .long("initial-authority")
+            .value_name("PUBKEY")
+            .validator(is_valid_pubkey)
+            .takes_value(true)
+            .help("Authority of the new account [default: anyone may update it]"),
+    ];
+
+    App::new(crate_name!())
+        .about(crate_description!())
+        .version(crate_version!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("config_file")
+                .short("C")
+                .long("config")
+                .value_name("PATH")
+                .takes_value(true)
+                .global(true)
+                .help("Solana CLI config file [default: the Solana CLI default]"),
+        )
+        .arg(
+            Arg::with_name("json_rpc_url")
+                .short("u")
+                .long("url")
+                .value_name("URL_OR_MONIKER")
+                .takes_value(true)
+                .global(true)
+                .validator(is_url_or_moniker)
+                .help("JSON RPC URL of the cluster [default: from the config]"),
+        )
+        .arg(
+            Arg::with_name("keypair")
+                .short("k")
+                .long("keypair")
+                .value_name("SIGNER")
+                .takes_value(true)
+                .global(true)
+                .validator(is_valid_signer)
+                .help("Fee payer [default: from the config]"),
+        )
+        .arg(
+            Arg::with_name("program_id")
+                .long("program-id")
+                .value_name("PUBKEY")
+                .takes_value(true)
+                .global(true)
+                .validator(is_valid_pubkey)
+                .help("Deployment of the data versioning program [default: its declared id]"),
+        )
+        .arg(
+            Arg::with_name("auto_migrate")
+                .long("auto-migrate")
+                .global(true)
+                .help("Migrate backlevel accounts in the transaction updating them"),
+        )
+        .subcommand(
+            SubCommand::with_name("create")
+                .about("Create and initialize a program account, paid by the fee payer")
+                .arg(
+                    Arg::with_name("account_keypair")
+                        .value_name("ACCOUNT_KEYPAIR")
+                        .validator(is_valid_signer)
+                        .required(true)
+                        .index(1)
+                        .help("Keypair of the new account"),
+                )
+                .args(&value_args),
+        )
+        .subcommand(
+            SubCommand::with_name("init")
+                .about("Initialize a program account that was created already")
+                .arg(account_arg.clone())
+                .args(&value_args),
+        )
+        .subcommand(
+            SubCommand::with_name("set-u64")
+                .about("Set somevalue")
+                .arg(account_arg.clone())
+                .arg(
+                    Arg::with_name("value")
+                        .value_name("U64")
+                        .validator(is_parsable::<u64>)
+                        .required(true)
+                        .index(2),
+                )
+                .arg(authority_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("set-string")
+                .about("Set somestring")
+                .arg(account_arg.clone())
+                .arg(
+                    Arg::with_name("value")
+                        .value_name("STRING")
+                        .required(true)
+                        .index(2),
+                )
+                .arg(authority_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("Show the decoded header and content of a program account")
+                .arg(account_arg.clone())
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .value_name("FORMAT")
+                        .possible_values(&["display", "json", "json-compact"])
+                        .default_value("display")
+                        .help("Output format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("close")
+                .about("Close a program account, withdrawing its lamports")
+                .arg(account_arg)
+                .arg(
+                    Arg::with_name("destination")
+                        .long("destination")
+                        .value_name("PUBKEY")
+                        .validator(is_valid_pubkey)
+                        .takes_value(true)
+                        .help("Recipient of the lamports [default: the fee payer]"),
+                )
+                .arg(authority_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("migrate")
+                .about("Migrate every program account stored below the current data version")
+                .arg(
+                    Arg::with_name("dry_run")
+                        .long("dry-run")
+                        .help("Only report the accounts that would be migrated"),
+                )
+                .arg(
+                    Arg::with_name("batch_size")
+                        .long("batch-size")
+                        .value_name("COUNT")
+                        .validator(is_parsable::<usize>)
+                        .default_value("8")
+                        .help("Accounts migrated by each transaction"),
+                )
+                .arg(
+                    Arg::with_name("parallel")
+                        .long("parallel")
+                        .value_name("COUNT")
+                        .validator(is_parsable::<usize>)
+                        .default_value("4")
+                        .help("Transactions in flight at once"),
+                )
+                .arg(
+                    Arg::with_name("checkpoint")
+                        .long("checkpoint")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("File listing the migrated accounts, which a rerun skips"),
+                )
+                .arg(
+                    Arg::with_name("upgrade_authority")
+                        .long("upgrade-authority")
+                        .value_name("SIGNER")
+                        .validator(is_valid_signer)
+                        .takes_value(true)
+                        .conflicts_with("authority")
+                        .help("Force migrate, frozen accounts included, as the upgrade authority"),
+                )
+                .arg(authority_arg),
+        )
+}
+
+/// Resolves the cluster and fee payer, flags taking precedence over the
+/// Solana CLI config
+fn load_config(
+    matches: &ArgMatches,
+    wallet_manager: &mut Option<Arc<RemoteWalletManager>>,
+) -> Result<Config, Box<dyn Error>> {
+    let cli_config = match matches.value_of("config_file") {
+        Some(config_file) => solana_cli_config::Config::load(config_file)
+            .map_err(|e| format!("could not load config file {}: {}", config_file, e))?,
+        None => solana_cli_config::CONFIG_FILE
+            .as_ref()
+            .and_then(|config_file| solana_cli_config::Config::load(config_file).ok())
+            .unwrap_or_default(),
+    };
+    let json_rpc_url = normalize_to_url_if_moniker(
+        matches
+            .value_of("json_rpc_url")
+            .unwrap_or(&cli_config.json_rpc_url),
+    );
+    let fee_payer = signer_from_path(
+        matches,
+        matches
+            .value_of("keypair")
+            .unwrap_or(&cli_config.keypair_path),
+        "keypair",
+        wallet_manager,
+    )?;
+    let commitment = CommitmentConfig::from_str(&cli_config.commitment)
+        .unwrap_or_else(|_| CommitmentConfig::confirmed());
+    let program_id = pubkey_of(matches, "program_id").unwrap_or_else(solana_data_versioning::id);
+    let client = DataVersionClient::new(
+        RpcTransport::new(RpcClient::new_with_commitment(json_rpc_url, commitment)),
+        program_id,
+    )
+    .with_auto_migrate(matches.is_present("auto_migrate"));
+    Ok(Config { client, fee_payer })
+}
+
+/// Address given by the argument, a pubkey or the pubkey of a signer
+fn account_of(
+    matches: &ArgMatches,
+    wallet_manager: &mut Option<Arc<RemoteWalletManager>>,
+) -> Result<Pubkey, Box<dyn Error>> {
+    Ok(pubkey_of_signer(matches, "account", wallet_manager)?.ok_or("ACCOUNT required")?)
+}
+
+/// Signers given by the `--authority` arguments
+fn authority_signers(
+    matches: &ArgMatches,
+    wallet_manager: &mut Option<Arc<RemoteWalletManager>>,
+) -> Result<Vec<Box<dyn Signer>>, Box<dyn Error>> {
+    matches
+        .values_of("authority")
+        .into_iter()
+        .flatten()
+        .map(|path| signer_from_path(matches, path, "authority", wallet_manager))
+        .collect()
+}
+
+fn print_update(outcome: &UpdateOutcome) {
+    if let Some(migration) = outcome.migration {
+        println!(
+            "Migrated from data version {} to {}",
+            migration.from_version, migration.to_version
+        );
+    }
+    println!("Signature: {}", outcome.signature);
+}
+
+fn field_json(value: &FieldValue) -> Value {
+    match value {
+        FieldValue::U64(value) => json!(value),
+        FieldValue::I64(value) => json!(value),
+        FieldValue::Bool(value) => json!(value),
+        FieldValue::String(value) => json!(value),
+        FieldValue::Pubkey(value) => json!(value.to_string()),
+    }
+}
+
+fn authority_json(authority: &AccountAuthority) -> Value {
+    match authority {
+        AccountAuthority::None => Value::Null,
+        AccountAuthority::Single(key) => json!({ "single": key.to_string() }),
+        AccountAuthority::Multisig { threshold, signers } => json!({
+            "multisig": {
+                "threshold": threshold,
+                "signers": signers.iter().map(Pubkey::to_string).collect::<Vec<_>>(),
+            }
+        }),
+    }
+}
+
+fn account_json(account: &StoredAccount) -> Value {
+    let state = &account.state;
+    let content = state.content();
+    json!({
+        "pubkey": account.pubkey.to_string(),
+        "lamports": account.lamports,
+        "header": {
+            "lifecycle": format!("{:?}", state.lifecycle()),
+            "storedVersion": account.stored_version,
+            "currentVersion": DATA_VERSION,
+            "writeSequence": state.write_sequence(),
+            "authority": authority_json(state.authority()),
+            "bumpSeed": state.bump_seed(),
+        },
+        "content": {
+            "somevalue": content.somevalue,
+            "somestring": content.somestring,
+            "fields": content
+                .fields
+                .iter()
+                .map(|(key, value)| (key.clone(), field_json(value)))
+                .collect::<Map<_, _>>(),
+        },
+    })
+}
+
+fn print_account(account: &StoredAccount) {
+    let state = &account.state;
+    let content = state.content();
+    println!("Account: {}", account.pubkey);
+    println!("Lamports: {}", account.lamports);
+    println!("Lifecycle: {:?}", state.lifecycle());
+    println!(
+        "Data version: {} (current {})",
+        account.stored_version, DATA_VERSION
+    );
+    println!("Write sequence: {}", state.write_sequence());
+    match state.authority() {
+        AccountAuthority::None => println!("Authority: none"),
+        AccountAuthority::Single(key) => println!("Authority: {}", key),
+        AccountAuthority::Multisig { threshold, signers } => println!(
+            "Authority: {} of {}",
+            threshold,
+            signers
+                .iter()
+                .map(Pubkey::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+    match state.bump_seed() {
+        Some(bump_seed) => println!("Bump seed: {}", bump_seed),
+        None => println!("Bump seed: none"),
+    }
+    println!("somevalue: {}", content.somevalue);
+    println!("somestring: {:?}", content.somestring);
+    println!("Fields: {}", content.fields.len());
+    for (key, value) in &content.fields {
+        println!("  {}: {}", key, field_json(value));
+    }
+}
+
+async fn process_command(
+    config: &Config,
+    sub_command: &str,
+    matches: &ArgMatches<'_>,
+    wallet_manager: &mut Option<Arc<RemoteWalletManager>>,
+) -> CliResult {
+    let client = &config.client;
+    let fee_payer = config.fee_payer.as_ref();
+    match sub_command {
+        "create" => {
+            let account = signer_from_path(
+                matches,
+                matches.value_of("account_keypair").unwrap(),
+                "account_keypair",
+                wallet_manager,
+            )?;
+            let signature = client
+                .create_account(
+                    fee_payer,
+                    account.as_ref(),
+                    value_t_or_exit!(matches, "value", u64),
+                    matches.value_of("string").unwrap().to_string(),
+                    pubkey_of(matches, "initial_authority").map(AccountAuthority::Single),
+                )
+                .await?;
+            println!("Account: {}", account.pubkey());
+            println!("Signature: {}", signature);
+        }
+        "init" => {
+            let signature = client
+                .initialize_account(
+                    fee_payer,
+                    &account_of(matches, wallet_manager)?,
+                    value_t_or_exit!(matches, "value", u64),
+                    matches.value_of("string").unwrap().to_string(),
+                    pubkey_of(matches, "initial_authority").map(AccountAuthority::Single),
+                )
+                .await?;
+            println!("Signature: {}", signature);
+        }
+        "set-u64" => {
+            let account = account_of(matches, wallet_manager)?;
+            let authorities = authority_signers(matches, wallet_manager)?;
+            let authorities = authorities.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+            let outcome = client
+                .set_u64_value(
+                    fee_payer,
+                    &account,
+                    &authorities,
+                    value_t_or_exit!(matches, "value", u64),
+                )
+                .await?;
+            print_update(&outcome);
+        }
+        "set-string" => {
+            let account = account_of(matches, wallet_manager)?;
+            let authorities = authority_signers(matches, wallet_manager)?;
+            let authorities = authorities.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+            let outcome = client
+                .set_string_value(
+                    fee_payer,
+                    &account,
+                    &authorities,
+                    matches.value_of("value").unwrap().to_string(),
+                )
+                .await?;
+            print_update(&outcome);
+        }
+        "show" => {
+            let account = client
+                .get_account(&account_of(matches, wallet_manager)?)
+                .await?;
+            match matches.value_of("output") {
+                Some("json") => {
+                    println!("{}", serde_json::to_string_pretty(&account_json(&account))?)
+                }
+                Some("json-compact") => println!("{}", account_json(&account)),
+                _ => print_account(&account),
+            }
+        }
+        "close" => {
+            let account = account_of(matches, wallet_manager)?;
+            let authorities = authority_signers(matches, wallet_manager)?;
+            let authorities = authorities.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+            let destination =
+                pubkey_of(matches, "destination").unwrap_or_else(|| fee_payer.pubkey());
+            let signature = client
+                .close_account(fee_payer, &account, &authorities, &destination)
+                .await?;
+            println!("Signature: {}", signature);
+        }
+        "migrate" => migrate::process_migrate(config, matches, wallet_manager).await?,
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let app_matches = app().get_matches();
+    // Global arguments are recorded with the subcommand they follow
+    let (sub_command, matches) = app_matches.subcommand();
+    let matches = matches.unwrap();
+    let mut wallet_manager = None;
+    let config = load_config(matches, &mut wallet_manager).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        exit(1);
+    });
+    if let Err(e) = process_command(&config, sub_command, matches, &mut wallet_manager).await {
+        eprintln!("error: {}", e);
+        exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_data_versioning::account_state::ProgramAccountState;
+
+    #[test]
+    fn test_authority_json_pass() {
+        let key = Pubkey::new_unique();
+        assert_eq!(authority_json(&AccountAuthority::None), Value::Null);
+        assert_eq!(
+            authority_json(&AccountAuthority::Single(key)),
+            json!({ "single": key.to_string() })
+        );
+        let signers = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        assert_eq!(
+            authority_json(&AccountAuthority::Multisig {
+                threshold: 1,
+                signers: signers.clone(),
+            }),
+            json!({
+                "multisig": {
+                    "threshold": 1,
+                    "signers": [signers[0].to_string(), signers[1].to_string()],
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_account_json_pass() {
+        let pubkey = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let mut state = ProgramAccountState::default();
+        state.set_initialized();
+        state.set_authority(AccountAuthority::Single(authority));
+        state.content_mut().somevalue = 42;
+        state.content_mut().somestring = "cli".to_string();
+        state
+            .set_field("limit".to_string(), FieldValue::U64(7))
+            .unwrap();
+        state
+            .set_field("owner".to_string(), FieldValue::Pubkey(authority))
+            .unwrap();
+        let account = StoredAccount {
+            pubkey,
+            lamports: 10,
+            stored_version: 4,
+            state,
+        };
+        assert_eq!(
+            account_json(&account),
+            json!({
+                "pubkey": pubkey.to_string(),
+                "lamports": 10,
+                "header": {
+                    "lifecycle": "Active",
+                    "storedVersion": 4,
+                    "currentVersion": DATA_VERSION,
+                    "writeSequence": 0,
+                    "authority": { "single": authority.to_string() },
+                    "bumpSeed": null,
+                },
+                "content": {
+                    "somevalue": 42,
+                    "somestring": "cli",
+                    "fields": {
+                        "limit": 7,
+                        "owner": authority.to_string(),
+                    },
+                },
+            })
+        );
+    }
 }
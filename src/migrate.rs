@@ -0,0 +1,400 @@
+//! `migrate` subcommand, finding the backlevel accounts of the program and
+//! migrating them in batches of instructions
+
+use crate::{authority_signers, CliResult, Config};
+use clap::{value_t_or_exit, ArgMatches};
+use futures::stream::{self, StreamExt};
+use solana_account_decoder::UiAccountEncoding;
+use solana_clap_utils::keypair::signer_from_path;
+use solana_client::{
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+};
+use solana_data_versioning::{
+    account_state::{AccountAuthority, AccountLifecycle, DATA_VERSION},
+    config::{find_config_address, ProgramConfig},
+    instruction,
+};
+use solana_remote_wallet::remote_wallet::RemoteWalletManager;
+use solana_sdk::{
+    account::Account, bs58, instruction::Instruction, pubkey::Pubkey, signature::Signer,
+};
+use std::{
+    collections::HashSet,
+    error::Error,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+};
+use versioning_solana_client::{ClientResult, StoredAccount};
+
+/// Offset of the data version byte in every layout of the program account
+const DATA_VERSION_OFFSET: usize = 1;
+
+/// Backlevel account and how it is migrated
+struct Candidate {
+    pubkey: Pubkey,
+    from_version: u8,
+    to_version: u8,
+    /// Keys of the given authorities that sign its migration
+    signers: Vec<Pubkey>,
+}
+
+/// Reason a backlevel account is left at its data version
+enum Skip {
+    Lifecycle(AccountLifecycle),
+    BelowMinimum,
+    Unauthorized,
+    Scheduled,
+    Invalid(String),
+}
+
+impl std::fmt::Display for Skip {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Skip::Lifecycle(AccountLifecycle::Frozen) => {
+                write!(f, "account is frozen, migrate with --upgrade-authority")
+            }
+            Skip::Lifecycle(lifecycle) => write!(f, "account is {:?}", lifecycle),
+            Skip::BelowMinimum => write!(
+                f,
+                "data version below the minimum readable, migrate with --upgrade-authority"
+            ),
+            Skip::Unauthorized => write!(f, "account authority has not been given"),
+            Skip::Scheduled => write!(f, "scheduled migration is not active yet"),
+            Skip::Invalid(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Accounts migrated by previous runs, appended to as batches confirm
+struct Checkpoint {
+    migrated: HashSet<Pubkey>,
+    file: Option<File>,
+}
+
+impl Checkpoint {
+    /// Reads the accounts listed in the checkpoint file, which is created
+    /// unless `read_only`
+    fn load(path: Option<&str>, read_only: bool) -> Result<Self, Box<dyn Error>> {
+        let mut migrated = HashSet::new();
+        let path = match path {
+            Some(path) => path,
+            None => {
+                return Ok(Checkpoint {
+                    migrated,
+                    file: None,
+                })
+            }
+        };
+        if Path::new(path).exists() {
+            for line in BufReader::new(File::open(path)?).lines() {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    migrated.insert(Pubkey::from_str(line.trim()).map_err(|e| {
+                        format!("invalid account {:?} in checkpoint {}: {}", line, path, e)
+                    })?);
+                }
+            }
+        }
+        let file = if read_only {
+            None
+        } else {
+            Some(OpenOptions::new().create(true).append(true).open(path)?)
+        };
+        Ok(Checkpoint { migrated, file })
+    }
+
+    /// Records migrated accounts, synced so an interrupted run resumes after them
+    fn record(&mut self, pubkeys: &[Pubkey]) -> Result<(), Box<dyn Error>> {
+        if let Some(file) = self.file.as_mut() {
+            for pubkey in pubkeys {
+                writeln!(file, "{}", pubkey)?;
+            }
+            file.sync_data()?;
+        }
+        self.migrated.extend(pubkeys);
+        Ok(())
+    }
+}
+
+/// Program accounts stored below `DATA_VERSION`, matching the data version
+/// byte of each older version in turn
+async fn find_backlevel_accounts(config: &Config) -> ClientResult<Vec<(Pubkey, Account)>> {
+    let program_id = *config.client.program_id();
+    let (config_pubkey, _) = find_config_address(&program_id);
+    config
+        .client
+        .transport()
+        .run(move |rpc_client| {
+            let mut accounts = Vec::new();
+            for version in 0..DATA_VERSION {
+                let filter = RpcFilterType::Memcmp(Memcmp {
+                    offset: DATA_VERSION_OFFSET,
+                    bytes: MemcmpEncodedBytes::Base58(bs58::encode([version]).into_string()),
+                    encoding: None,
+                });
+                accounts.extend(
+                    rpc_client
+                        .get_program_accounts_with_config(
+                            &program_id,
+                            RpcProgramAccountsConfig {
+                                filters: Some(vec![filter]),
+                                account_config: RpcAccountInfoConfig {
+                                    encoding: Some(UiAccountEncoding::Base64),
+                                    commitment: Some(rpc_client.commitment()),
+                                    ..RpcAccountInfoConfig::default()
+                                },
+                                ..RpcProgramAccountsConfig::default()
+                            },
+                        )?
+                        .into_iter()
+                        // The config account shares the owner but not the layout
+                        .filter(|(pubkey, _)| *pubkey != config_pubkey),
+                );
+            }
+            Ok(accounts)
+        })
+        .await
+}
+
+/// Keys of the authorities that satisfy the account authority, None if
+/// they do not
+fn signers_for(authority: &AccountAuthority, authorities: &[Pubkey]) -> Option<Vec<Pubkey>> {
+    match authority {
+        AccountAuthority::None => Some(vec![]),
+        AccountAuthority::Single(key) => authorities.contains(key).then(|| vec![*key]),
+        AccountAuthority::Multisig { threshold, signers } => {
+            let signed = signers
+                .iter()
+                .filter(|key| authorities.contains(key))
+                .copied()
+                .collect::<Vec<_>>();
+            (signed.len() >= *threshold as usize).then_some(signed)
+        }
+    }
+}
+
+/// Decides how the account is migrated, mirroring the checks the program
+/// makes of `MigrateAccount`, or of `ForceMigrate` when `forced`
+fn plan(
+    config: &Config,
+    program_config: &ProgramConfig,
+    slot: u64,
+    (pubkey, account): &(Pubkey, Account),
+    authorities: &[Pubkey],
+    forced: bool,
+) -> Result<Candidate, Skip> {
+    let stored = StoredAccount::decode(config.client.program_id(), pubkey, account, program_config)
+        .map_err(|e| Skip::Invalid(e.to_string()))?;
+    let from_version = stored.stored_version;
+    let to_version = program_config
+        .write_version(from_version, || Ok(slot))
+        .map_err(|e| Skip::Invalid(e.to_string()))?;
+    if to_version == from_version {
+        return Err(Skip::Scheduled);
+    }
+    let lifecycle = stored.state.lifecycle();
+    let signers = if forced {
+        if matches!(
+            lifecycle,
+            AccountLifecycle::Uninitialized | AccountLifecycle::Closed
+        ) {
+            return Err(Skip::Lifecycle(lifecycle));
+        }
+        vec![]
+    } else {
+        if !matches!(
+            lifecycle,
+            AccountLifecycle::Active | AccountLifecycle::PendingMigration
+        ) {
+            return Err(Skip::Lifecycle(lifecycle));
+        }
+        if from_version < program_config.min_readable_version {
+            return Err(Skip::BelowMinimum);
+        }
+        signers_for(stored.state.authority(), authorities).ok_or(Skip::Unauthorized)?
+    };
+    Ok(Candidate {
+        pubkey: *pubkey,
+        from_version,
+        to_version,
+        signers,
+    })
+}
+
+/// Migrates the backlevel accounts, or with `--dry-run` only reports them
+pub async fn process_migrate(
+    config: &Config,
+    matches: &ArgMatches<'_>,
+    wallet_manager: &mut Option<Arc<RemoteWalletManager>>,
+) -> CliResult {
+    let dry_run = matches.is_present("dry_run");
+    let batch_size = value_t_or_exit!(matches, "batch_size", usize);
+    let parallel = value_t_or_exit!(matches, "parallel", usize);
+    if batch_size == 0 || parallel == 0 {
+        return Err("--batch-size and --parallel must be at least 1".into());
+    }
+    let authorities = authority_signers(matches, wallet_manager)?;
+    let upgrade_authority = matches
+        .value_of("upgrade_authority")
+        .map(|path| signer_from_path(matches, path, "upgrade_authority", wallet_manager))
+        .transpose()?;
+    let mut checkpoint = Checkpoint::load(matches.value_of("checkpoint"), dry_run)?;
+
+    let program_config = config.client.get_config().await?;
+    if program_config.migrations_paused && upgrade_authority.is_none() {
+        println!("Migrations are paused by the program config");
+        if !dry_run {
+            return Err("migrations are paused".into());
+        }
+    }
+    let slot = config
+        .client
+        .transport()
+        .run(|rpc_client| Ok(rpc_client.get_slot()?))
+        .await?;
+    let authority_keys = authorities
+        .iter()
+        .map(|signer| signer.pubkey())
+        .collect::<Vec<_>>();
+
+    let accounts = find_backlevel_accounts(config).await?;
+    println!(
+        "Found {} accounts below data version {}",
+        accounts.len(),
+        DATA_VERSION
+    );
+    let mut candidates = Vec::new();
+    let (mut regressed, mut skipped) = (0, 0);
+    for account in &accounts {
+        // The scan only returns accounts still stored below DATA_VERSION,
+        // so one the checkpoint lists was written back since, and is
+        // migrated again rather than trusted to the checkpoint
+        if checkpoint.migrated.contains(&account.0) {
+            regressed += 1;
+            println!(
+                "{} is in the checkpoint but stored at data version {}",
+                account.0, account.1.data[DATA_VERSION_OFFSET]
+            );
+        }
+        match plan(
+            config,
+            &program_config,
+            slot,
+            account,
+            &authority_keys,
+            upgrade_authority.is_some(),
+        ) {
+            Ok(candidate) => {
+                if dry_run {
+                    println!(
+                        "Would migrate {} from data version {} to {}",
+                        candidate.pubkey, candidate.from_version, candidate.to_version
+                    );
+                }
+                candidates.push(candidate);
+            }
+            Err(skip) => {
+                skipped += 1;
+                println!("Skipping {}: {}", account.0, skip);
+            }
+        }
+    }
+    if regressed > 0 {
+        println!(
+            "{} accounts in the checkpoint are below data version {} again",
+            regressed, DATA_VERSION
+        );
+    }
+    if dry_run {
+        println!(
+            "Dry run: {} accounts to migrate, {} skipped",
+            candidates.len(),
+            skipped
+        );
+        return Ok(());
+    }
+
+    let program_id = config.client.program_id();
+    let fee_payer = config.fee_payer.as_ref();
+    let batches = candidates
+        .chunks(batch_size)
+        .map(|batch| {
+            let instructions = batch
+                .iter()
+                .map(|candidate| match &upgrade_authority {
+                    Some(upgrade_authority) => instruction::force_migrate(
+                        program_id,
+                        &candidate.pubkey,
+                        &upgrade_authority.pubkey(),
+                    ),
+                    None => instruction::migrate_account(
+                        program_id,
+                        &candidate.pubkey,
+                        &candidate.signers.iter().collect::<Vec<_>>(),
+                    ),
+                })
+                .collect::<Vec<Instruction>>();
+            // Each authority signs once for every account of the batch
+            let signers = match &upgrade_authority {
+                Some(upgrade_authority) => vec![upgrade_authority.as_ref()],
+                None => authorities
+                    .iter()
+                    .filter(|signer| {
+                        batch
+                            .iter()
+                            .any(|candidate| candidate.signers.contains(&signer.pubkey()))
+                    })
+                    .map(AsRef::as_ref)
+                    .collect::<Vec<&dyn Signer>>(),
+            };
+            async move {
+                let result = config
+                    .client
+                    .send_instructions(&instructions, fee_payer, &signers)
+                    .await;
+                (batch, result)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let (mut migrated, mut failed) = (0, 0);
+    let mut results = stream::iter(batches).buffer_unordered(parallel);
+    while let Some((batch, result)) = results.next().await {
+        match result {
+            Ok(signature) => {
+                for candidate in batch {
+                    println!(
+                        "Migrated {} from data version {} to {}",
+                        candidate.pubkey, candidate.from_version, candidate.to_version
+                    );
+                }
+                println!("Signature: {}", signature);
+                checkpoint.record(
+                    &batch
+                        .iter()
+                        .map(|candidate| candidate.pubkey)
+                        .collect::<Vec<_>>(),
+                )?;
+                migrated += batch.len();
+            }
+            Err(e) => {
+                for candidate in batch {
+                    eprintln!("Failed to migrate {}: {}", candidate.pubkey, e);
+                }
+                failed += batch.len();
+            }
+        }
+    }
+    println!(
+        "Migrated {} accounts, {} failed, {} skipped",
+        migrated, failed, skipped
+    );
+    if failed > 0 {
+        return Err(format!("{} accounts failed to migrate", failed).into());
+    }
+    Ok(())
+}
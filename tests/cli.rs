@@ -0,0 +1,140 @@
+use common::{clean_ledger_setup_validator, get_account_for_key, new_client, PROG_KEY};
+use serde_json::Value;
+use solana_data_versioning::account_state::{ACCOUNT_STATE_SPACE, DATA_VERSION};
+use solana_sdk::{
+    signature::{write_keypair_file, Keypair},
+    signer::Signer,
+    system_instruction,
+    transaction::Transaction,
+};
+use std::{
+    fs,
+    process::{Command, Output},
+};
+
+mod common;
+
+const WALLET_KEYPAIR: &str = "./keys/accounts/version_wallet.json";
+const ACCOUNT_KEYPAIR_PATH: &str = "./.ledger-cli-account.json";
+
+/// Runs the CLI against the validator, paid by the wallet
+fn cli(rpc_url: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_versioning-solana"))
+        .args(["--url", rpc_url, "--keypair", WALLET_KEYPAIR])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Runs the CLI, expecting it to succeed, and returns its output
+fn cli_ok(rpc_url: &str, args: &[&str]) -> String {
+    let output = cli(rpc_url, args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Decodes the account as `show --output json` prints it
+fn show_json(rpc_url: &str, account: &str) -> Value {
+    serde_json::from_str(&cli_ok(rpc_url, &["show", account, "--output", "json"])).unwrap()
+}
+
+#[test]
+fn test_cli_account_round_trip_pass() {
+    let (test_validator, wallet) = clean_ledger_setup_validator().unwrap();
+    let client = new_client(&test_validator);
+    let rpc_url = test_validator.rpc_url();
+    let wallet_key = wallet.pubkey().to_string();
+
+    // Create an account the wallet is the authority of
+    let account = Keypair::new();
+    let account_key = account.pubkey().to_string();
+    write_keypair_file(&account, ACCOUNT_KEYPAIR_PATH).unwrap();
+    let report = cli_ok(
+        &rpc_url,
+        &[
+            "create",
+            ACCOUNT_KEYPAIR_PATH,
+            "--value",
+            "5",
+            "--string",
+            "hello",
+            "--initial-authority",
+            &wallet_key,
+        ],
+    );
+    fs::remove_file(ACCOUNT_KEYPAIR_PATH).unwrap();
+    assert!(report.contains(&format!("Account: {}", account_key)));
+    let shown = show_json(&rpc_url, &account_key);
+    assert_eq!(shown["pubkey"], account_key);
+    assert_eq!(shown["header"]["lifecycle"], "Active");
+    assert_eq!(shown["header"]["storedVersion"], DATA_VERSION);
+    assert_eq!(shown["header"]["authority"]["single"], wallet_key);
+    assert_eq!(shown["content"]["somevalue"], 5);
+    assert_eq!(shown["content"]["somestring"], "hello");
+    let write_sequence = shown["header"]["writeSequence"].as_u64().unwrap();
+
+    // Updates need the authority
+    assert!(!cli(&rpc_url, &["set-u64", &account_key, "9"])
+        .status
+        .success());
+    cli_ok(
+        &rpc_url,
+        &["set-u64", &account_key, "9", "--authority", WALLET_KEYPAIR],
+    );
+    cli_ok(
+        &rpc_url,
+        &[
+            "set-string",
+            &account_key,
+            "world",
+            "--authority",
+            WALLET_KEYPAIR,
+        ],
+    );
+    let report = cli_ok(&rpc_url, &["show", &account_key]);
+    assert!(report.contains("somevalue: 9"));
+    assert!(report.contains("somestring: \"world\""));
+    let shown = show_json(&rpc_url, &account_key);
+    assert_eq!(shown["header"]["writeSequence"], write_sequence + 2);
+
+    cli_ok(
+        &rpc_url,
+        &["close", &account_key, "--authority", WALLET_KEYPAIR],
+    );
+    assert!(get_account_for_key(&client, &account.pubkey()).is_none());
+
+    // Initialize an account created outside the CLI
+    let account = Keypair::new();
+    let rpc_client = client.transport().rpc_client();
+    let transaction = Transaction::new_signed_with_payer(
+        &[system_instruction::create_account(
+            &wallet.pubkey(),
+            &account.pubkey(),
+            rpc_client
+                .get_minimum_balance_for_rent_exemption(ACCOUNT_STATE_SPACE)
+                .unwrap(),
+            ACCOUNT_STATE_SPACE as u64,
+            &PROG_KEY,
+        )],
+        Some(&wallet.pubkey()),
+        &[&wallet, &account],
+        rpc_client.get_latest_blockhash().unwrap(),
+    );
+    rpc_client
+        .send_and_confirm_transaction(&transaction)
+        .unwrap();
+    let account_key = account.pubkey().to_string();
+    cli_ok(&rpc_url, &["init", &account_key, "--value", "3"]);
+    let shown: Value = serde_json::from_str(&cli_ok(
+        &rpc_url,
+        &["show", &account_key, "--output", "json-compact"],
+    ))
+    .unwrap();
+    assert_eq!(shown["header"]["authority"], Value::Null);
+    assert_eq!(shown["content"]["somevalue"], 3);
+    assert_eq!(shown["content"]["somestring"], "");
+}
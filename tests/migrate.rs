@@ -0,0 +1,207 @@
+use common::{
+    clean_ledger_setup_validator, clean_ledger_setup_validator_with_accounts, get_account_for_key,
+    get_keypair, new_client, PROG_KEY, USER1_ACCOUNT, USER2_ACCOUNT,
+};
+use solana_data_versioning::{
+    account_state::{
+        AccountAuthority, AccountLifecycle, ProgramAccountState, ACCOUNT_STATE_SPACE, DATA_VERSION,
+        OLDEST_WRITABLE_VERSION,
+    },
+    admin::find_program_data_address,
+};
+use solana_program::{
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use solana_sdk::{account::Account, signer::Signer};
+use std::{fs, path::Path, process::Command};
+
+mod common;
+
+const WALLET_KEYPAIR: &str = "./keys/accounts/version_wallet.json";
+const CHECKPOINT_PATH: &str = "./.ledger-migrate-checkpoint";
+/// Authority of the account that needs one to migrate
+const AUTHORITY_KEYPAIR: &str = "./keys/accounts/user1_account.json";
+/// Upgrade authority recorded in the program data
+const UPGRADE_AUTHORITY_KEYPAIR: &str = "./keys/accounts/user2_account.json";
+
+/// Runs the migrate subcommand against the validator
+fn migrate(rpc_url: &str, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_versioning-solana"))
+        .args(["--url", rpc_url, "--keypair", WALLET_KEYPAIR, "migrate"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_migrate_backlevel_accounts_pass() {
+    let (test_validator, _initial_keypair) = clean_ledger_setup_validator().unwrap();
+    let client = new_client(&test_validator);
+    let rpc_url = test_validator.rpc_url();
+    let user_keys = [
+        get_keypair(USER1_ACCOUNT).unwrap().pubkey(),
+        get_keypair(USER2_ACCOUNT).unwrap().pubkey(),
+    ];
+    let _ = fs::remove_file(CHECKPOINT_PATH);
+
+    // A dry run leaves the stored accounts at data version 0
+    let report = migrate(&rpc_url, &["--dry-run", "--checkpoint", CHECKPOINT_PATH]);
+    assert!(report.contains("Dry run: 2 accounts to migrate, 0 skipped"));
+    for key in &user_keys {
+        assert!(report.contains(&format!("Would migrate {} from data version 0", key)));
+        assert_eq!(get_account_for_key(&client, key).unwrap().data[1], 0);
+    }
+    assert!(!Path::new(CHECKPOINT_PATH).exists());
+
+    let report = migrate(
+        &rpc_url,
+        &["--batch-size", "1", "--checkpoint", CHECKPOINT_PATH],
+    );
+    assert!(report.contains("Migrated 2 accounts, 0 failed, 0 skipped"));
+    let checkpoint = fs::read_to_string(CHECKPOINT_PATH).unwrap();
+    for key in &user_keys {
+        assert_eq!(
+            get_account_for_key(&client, key).unwrap().data[1],
+            DATA_VERSION
+        );
+        assert!(checkpoint.contains(&key.to_string()));
+    }
+
+    // Nothing is left below the current data version
+    let report = migrate(&rpc_url, &["--checkpoint", CHECKPOINT_PATH]);
+    assert!(report.contains("Found 0 accounts"));
+    fs::remove_file(CHECKPOINT_PATH).unwrap();
+}
+
+/// Program account stored at the oldest writable data version
+fn backlevel_account(lifecycle: AccountLifecycle, authority: AccountAuthority) -> Account {
+    let mut state = ProgramAccountState::default();
+    state.set_lifecycle(lifecycle);
+    state.set_authority(authority);
+    state.set_version(OLDEST_WRITABLE_VERSION).unwrap();
+    let mut data = vec![0_u8; ACCOUNT_STATE_SPACE];
+    state.pack_into_slice(&mut data);
+    Account {
+        lamports: Rent::default().minimum_balance(ACCOUNT_STATE_SPACE),
+        data,
+        owner: PROG_KEY,
+        ..Account::default()
+    }
+}
+
+/// Program data recording the upgrade authority, as the upgradeable loader
+/// keeps it
+fn program_data(upgrade_authority: Pubkey) -> Account {
+    let data = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+        slot: 0,
+        upgrade_authority_address: Some(upgrade_authority),
+    })
+    .unwrap();
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: bpf_loader_upgradeable::id(),
+        ..Account::default()
+    }
+}
+
+#[test]
+fn test_migrate_skip_resume_and_force_pass() {
+    let authorized = Pubkey::new_unique();
+    let frozen = Pubkey::new_unique();
+    let authority = get_keypair(USER1_ACCOUNT).unwrap().pubkey();
+    let upgrade_authority = get_keypair(USER2_ACCOUNT).unwrap().pubkey();
+    let (test_validator, _initial_keypair) = clean_ledger_setup_validator_with_accounts(vec![
+        (
+            authorized,
+            backlevel_account(
+                AccountLifecycle::Active,
+                AccountAuthority::Single(authority),
+            ),
+        ),
+        (
+            frozen,
+            backlevel_account(AccountLifecycle::Frozen, AccountAuthority::None),
+        ),
+        (
+            find_program_data_address(&PROG_KEY),
+            program_data(upgrade_authority),
+        ),
+    ])
+    .unwrap();
+    let client = new_client(&test_validator);
+    let rpc_url = test_validator.rpc_url();
+    let data_version = |key: &Pubkey| get_account_for_key(&client, key).unwrap().data[1];
+    let user_keys = [authority, upgrade_authority];
+
+    // Without its authority, and frozen, accounts are skipped
+    let report = migrate(&rpc_url, &["--dry-run"]);
+    assert!(report.contains("Found 4 accounts"));
+    assert!(report.contains(&format!(
+        "Skipping {}: account authority has not been given",
+        authorized
+    )));
+    assert!(report.contains(&format!(
+        "Skipping {}: account is frozen, migrate with --upgrade-authority",
+        frozen
+    )));
+    assert!(report.contains("Dry run: 2 accounts to migrate, 2 skipped"));
+
+    // A rerun migrates everything still backlevel, including accounts the
+    // checkpoint lists
+    fs::write(CHECKPOINT_PATH, format!("{}\n", user_keys[0])).unwrap();
+    let report = migrate(&rpc_url, &["--checkpoint", CHECKPOINT_PATH]);
+    assert!(report.contains(&format!(
+        "{} is in the checkpoint but stored at data version 0",
+        user_keys[0]
+    )));
+    assert!(report.contains(&format!(
+        "1 accounts in the checkpoint are below data version {} again",
+        DATA_VERSION
+    )));
+    assert!(report.contains("Migrated 2 accounts, 0 failed, 2 skipped"));
+    assert_eq!(data_version(&user_keys[0]), DATA_VERSION);
+    assert_eq!(data_version(&user_keys[1]), DATA_VERSION);
+    let checkpoint = fs::read_to_string(CHECKPOINT_PATH).unwrap();
+    assert!(checkpoint.contains(&user_keys[1].to_string()));
+    fs::remove_file(CHECKPOINT_PATH).unwrap();
+
+    // The authority signs for the account it is the authority of
+    let report = migrate(&rpc_url, &["--authority", AUTHORITY_KEYPAIR]);
+    assert!(report.contains("Migrated 1 accounts, 0 failed, 1 skipped"));
+    assert_eq!(data_version(&user_keys[0]), DATA_VERSION);
+    assert_eq!(data_version(&authorized), DATA_VERSION);
+    assert_eq!(data_version(&frozen), OLDEST_WRITABLE_VERSION);
+
+    // The upgrade authority force migrates the frozen account, which stays
+    // frozen
+    let report = migrate(
+        &rpc_url,
+        &["--upgrade-authority", UPGRADE_AUTHORITY_KEYPAIR],
+    );
+    assert!(report.contains(&format!(
+        "Migrated {} from data version {} to {}",
+        frozen, OLDEST_WRITABLE_VERSION, DATA_VERSION
+    )));
+    assert!(report.contains("Migrated 1 accounts, 0 failed, 0 skipped"));
+    let account = get_account_for_key(&client, &frozen).unwrap();
+    assert_eq!(account.data[1], DATA_VERSION);
+    assert_eq!(
+        ProgramAccountState::unpack_from_slice(&account.data)
+            .unwrap()
+            .lifecycle(),
+        AccountLifecycle::Frozen
+    );
+
+    let report = migrate(&rpc_url, &[]);
+    assert!(report.contains("Found 0 accounts"));
+}